@@ -1,6 +1,6 @@
 use color_eyre::eyre::Result;
 
-use crate::package_service::PackageService;
+use hermione::package_service::PackageService;
 
 /// CLI Action Trait
 pub trait Action {
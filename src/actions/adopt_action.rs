@@ -0,0 +1,83 @@
+use color_eyre::eyre::{eyre, Result};
+use url::Url;
+
+use std::fs;
+use std::path::PathBuf;
+
+use hermione::adopter::Adopter;
+use hermione::events::Event;
+use hermione::manifest::Manifest;
+use hermione::package_service::PackageService;
+
+use crate::action::Action;
+
+/// Adopt Action moves existing files into a package and installs the package in their place.
+pub struct AdoptAction {
+    pub paths: Vec<String>,
+    pub package_path: String,
+}
+
+impl Action for AdoptAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let package_path_buf = fs::canonicalize(&self.package_path)?;
+        let manifest_path = Manifest::path_in(&package_path_buf)?;
+        let original_manifest = fs::read_to_string(&manifest_path).ok();
+
+        let adopter = Adopter::new(package_path_buf.clone());
+        let adopted = adopter.adopt(
+            &package_service,
+            self.paths.iter().map(PathBuf::from).collect(),
+        )?;
+        let manifest = Manifest::new_from_path(manifest_path.clone())?;
+
+        // Version installed before, kept by uninstall so it can be restored
+        let mut previous = None;
+        if let Ok(installed_package) = package_service
+            .clone()
+            .get_installed_package(manifest.id.clone())
+        {
+            events.emit(Event::info(format!(
+                "Unlinking installed package {}",
+                manifest.id
+            )));
+            installed_package.uninstall()?;
+            previous = Some(installed_package);
+        }
+
+        let package_source = Url::from_directory_path(&package_path_buf)
+            .map_err(|_| eyre!("Unable to build source for {}", package_path_buf.display()))?
+            .to_string();
+        match package_service.clone().download_and_install(package_source) {
+            Ok(_) => {
+                events.emit(Event::success(format!(
+                    "Adopted {} file(s) into {}",
+                    adopted.len(),
+                    manifest.id
+                )));
+                Ok(())
+            }
+            Err(e) => {
+                events.emit(Event::error("Install failed, moving adopted files back"));
+                Adopter::revert(adopted)?;
+                if let Some(original_manifest) = original_manifest {
+                    fs::write(&manifest_path, original_manifest)?;
+                }
+                if let Some(previous) = previous {
+                    events.emit(Event::info(format!(
+                        "Reinstalling previous version of {}",
+                        manifest.id
+                    )));
+                    if let Err(restore_error) = previous.restore() {
+                        events.emit(Event::error(format!(
+                            "Unable to reinstall previous version of {}: {:#}",
+                            manifest.id, restore_error
+                        )));
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+}
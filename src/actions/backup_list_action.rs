@@ -0,0 +1,32 @@
+use color_eyre::eyre::Result;
+
+use hermione::backup::Backup;
+use hermione::events::Event;
+use hermione::package_service::PackageService;
+
+use crate::action::Action;
+
+/// Backup List Action displays the files Hermione moved aside while installing packages.
+pub struct BackupListAction {}
+
+impl Action for BackupListAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let backups = Backup::list(&package_service.backup_dir())?;
+        backups.iter().for_each(|backup| {
+            events.emit(Event::detail(format!(
+                "{} | {} | {} | {}",
+                backup.id,
+                backup.package_id,
+                backup.created_at,
+                backup.original_path.display()
+            )));
+        });
+        events.emit(Event::success(format!(
+            "Displayed: {} Backups",
+            backups.len()
+        )));
+        package_service.output.set("backups", backups)
+    }
+}
@@ -0,0 +1,23 @@
+use color_eyre::eyre::Result;
+
+use hermione::backup::Backup;
+use hermione::events::Event;
+use hermione::package_service::PackageService;
+
+use crate::action::Action;
+
+/// Backup Restore Action moves a backed up file back to where it was found.
+pub struct BackupRestoreAction {
+    pub backup_id: String,
+}
+
+impl Action for BackupRestoreAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let backup = Backup::find(&package_service.backup_dir(), &self.backup_id)?;
+        package_service.restore_backup(backup)?;
+        events.emit(Event::success("Restore successful"));
+        Ok(())
+    }
+}
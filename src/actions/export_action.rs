@@ -0,0 +1,52 @@
+use color_eyre::eyre::{Result, WrapErr};
+
+use hermione::events::Event;
+use hermione::hermfile::Hermfile;
+use hermione::package_service::PackageService;
+use hermione::plan::PlannedOperation;
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::action::Action;
+
+/// Export Action writes the installed packages out as a Hermfile pinned to exact versions.
+pub struct ExportAction {
+    /// File to write, the Hermfile is printed to stdout when missing.
+    pub path: Option<PathBuf>,
+}
+
+impl Action for ExportAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        let hermfile = Hermfile::export(&package_service)?;
+        package_service
+            .output
+            .set("packages", hermfile.packages.clone())?;
+
+        match self.path {
+            Some(path) => {
+                events.emit(Event::info("Initialized"));
+                let contents = hermfile.to_string_for_path(&path)?;
+                package_service.perform(PlannedOperation::Write { path: path.clone() }, || {
+                    fs::write(&path, contents)
+                        .wrap_err_with(|| format!("Unable to write {}", path.display()))
+                })?;
+                events.emit(Event::success(format!(
+                    "Exported {} Packages to {}",
+                    hermfile.packages.len(),
+                    path.display()
+                )));
+            }
+            // Only the Hermfile goes to stdout so it can be redirected into a file
+            None if !package_service.output.is_json() => {
+                print!(
+                    "{}",
+                    hermfile.to_string_for_path(&PathBuf::from("Hermfile"))?
+                );
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use color_eyre::eyre::Result;
+
+use hermione::events::Event;
+use hermione::history::History;
+use hermione::package_service::PackageService;
+
+use crate::action::Action;
+
+/// History Action displays what Hermione did, oldest first.
+pub struct HistoryAction {
+    /// Only show operations on this package.
+    pub package_id: Option<String>,
+}
+
+impl Action for HistoryAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let entries = History::new(&package_service)
+            .entries()?
+            .into_iter()
+            .filter(|entry| match &self.package_id {
+                Some(package_id) => &entry.package == package_id,
+                None => true,
+            })
+            .collect::<Vec<_>>();
+        for entry in &entries {
+            events.emit(Event::detail(entry.to_string()));
+        }
+        events.emit(Event::success(format!(
+            "Displayed: {} Operations",
+            entries.len()
+        )));
+        package_service.output.set("history", entries)
+    }
+}
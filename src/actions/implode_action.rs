@@ -1,8 +1,10 @@
-use color_eyre::eyre::{eyre, Result};
-use paris::Logger;
+use color_eyre::eyre::Result;
+
+use hermione::error::HermioneError;
+use hermione::events::Event;
+use hermione::package_service::PackageService;
 
 use crate::action::Action;
-use crate::package_service::PackageService;
 
 /// Implode Action removes all installed packages and all downloaded packages,
 /// and cleans up the install directory and the download directory.
@@ -12,15 +14,18 @@ pub struct ImplodeAction {
 
 impl Action for ImplodeAction {
     fn execute(self, package_service: PackageService) -> Result<()> {
-        let mut logger = Logger::new();
-        logger.info("Initialized");
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
         if self.yes_i_am_sure {
             package_service.implode()?;
-            logger.success("Successfully removed everything Hermione");
+            events.emit(Event::success("Successfully removed everything Hermione"));
             Ok(())
         } else {
-            logger.error("I am not sure you want me to do this.");
-            Err(eyre!("Please pass confirm flag if you are sure"))
+            events.emit(Event::error("I am not sure you want me to do this."));
+            Err(
+                HermioneError::Policy(String::from("Please pass confirm flag if you are sure"))
+                    .into(),
+            )
         }
     }
 }
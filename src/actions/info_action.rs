@@ -0,0 +1,54 @@
+use color_eyre::eyre::Result;
+
+use hermione::events::Event;
+use hermione::package_info::PackageInfo;
+use hermione::package_service::PackageService;
+
+use crate::action::Action;
+
+/// Info Action shows everything about a package, whether it is installed, a package file,
+/// a package directory or listed in a repository.
+pub struct InfoAction {
+    pub target: String,
+}
+
+impl Action for InfoAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let info = PackageInfo::resolve(&package_service, &self.target)?;
+
+        events.emit(Event::info(format!(
+            "{} <blue>{}</> @ {}",
+            info.name, info.id, info.version
+        )));
+        events.emit(Event::detail(info.description.clone()));
+        events.emit(Event::detail(format!(
+            "Authors: {}",
+            info.authors.join(", ")
+        )));
+        if let Some(source) = &info.source {
+            events.emit(Event::detail(format!("Source: {}", source)));
+        }
+        match &info.installed_at {
+            Some(installed_at) => {
+                events.emit(Event::detail(format!("Installed: {}", installed_at)))
+            }
+            None => events.emit(Event::detail("Installed: no")),
+        }
+        if let Some(pin) = &info.pin {
+            events.emit(Event::detail(format!("Pinned: {}", pin)));
+        }
+        if info.hooks.is_empty() {
+            events.emit(Event::detail("Hooks: none"));
+        } else {
+            events.emit(Event::detail(format!("Hooks: {}", info.hooks.join(", "))));
+        }
+        events.emit(Event::detail(format!("Mappings: {}", info.mappings.len())));
+        for mapping in &info.mappings {
+            events.emit(Event::detail(format!("  {}", mapping.display_line())));
+        }
+
+        package_service.output.set("package", info)
+    }
+}
@@ -1,21 +1,28 @@
 use color_eyre::eyre::Result;
-use paris::Logger;
 
 use std::path::Path;
 
+use hermione::events::Event;
+use hermione::manifest::ManifestFormat;
+use hermione::package_service::PackageService;
+use hermione::scaffold::Scaffold;
+
 use crate::action::Action;
-use crate::package_service::PackageService;
-use crate::scaffold::Scaffold;
 
-/// Init Action creates a template `hermione.yml` file in the current directory it is ran.
-pub struct InitAction {}
+/// Init Action creates a template manifest file in the current directory it is ran.
+pub struct InitAction {
+    /// File format of the manifest, `hermione.yml` for YAML.
+    pub format: ManifestFormat,
+}
 
 impl Action for InitAction {
-    fn execute(self, _package_service: PackageService) -> Result<()> {
-        let mut logger = Logger::new();
-        logger.info("Initialized");
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
         let initialize_path = Path::new(".");
-        let scaffold = Scaffold::new("<Package Name>", "com.example.package");
+        let scaffold = Scaffold::new("<Package Name>", "com.example.package")
+            .with_events(events)
+            .with_format(self.format);
         scaffold.create_manifest(initialize_path.to_path_buf())
     }
 }
@@ -0,0 +1,89 @@
+use color_eyre::eyre::Result;
+
+use hermione::error::HermioneError;
+use hermione::events::Event;
+use hermione::package_service::PackageService;
+use hermione::packer::Packer;
+
+use std::path::PathBuf;
+
+use crate::action::Action;
+
+/// Inspect Action shows what a package archive contains without installing it.
+pub struct InspectAction {
+    pub package_path: PathBuf,
+}
+
+impl Action for InspectAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let inspection = Packer::new(self.package_path.clone()).inspect()?;
+
+        events.emit(Event::info(format!(
+            "Entries of {}",
+            self.package_path.display()
+        )));
+        for entry in &inspection.entries {
+            events.emit(Event::detail(entry.to_string()));
+        }
+
+        events.emit(Event::info("Manifest"));
+        for line in inspection.manifest_text.lines() {
+            events.emit(Event::detail(String::from(line)));
+        }
+
+        events.emit(Event::info("Mapping integrity"));
+        for mapping in &inspection.mappings {
+            events.emit(Event::detail(mapping.display_line()));
+        }
+        if !inspection.files.is_empty() {
+            events.emit(Event::info("Included file integrity"));
+            for file in &inspection.files {
+                events.emit(Event::detail(file.display_line()));
+            }
+        }
+        for path in &inspection.unreferenced {
+            events.emit(Event::warning(format!(
+                "{} is not referenced by any mapping or included file",
+                path
+            )));
+        }
+
+        if let Some(hooks) = &inspection.manifest.hooks {
+            for (hook, script) in &[
+                ("pre_install", &hooks.pre_install),
+                ("post_install", &hooks.post_install),
+                ("pre_remove", &hooks.pre_remove),
+                ("post_remove", &hooks.post_remove),
+            ] {
+                if let Some(script) = script {
+                    events.emit(Event::info(format!("{} hook", hook)));
+                    for line in script.lines() {
+                        events.emit(Event::detail(String::from(line)));
+                    }
+                }
+            }
+        }
+
+        let failures = inspection
+            .failures()
+            .iter()
+            .map(|mapping| format!("{} ({})", mapping.input, mapping.state))
+            .collect::<Vec<_>>();
+        package_service.output.set("inspection", &inspection)?;
+        if failures.is_empty() {
+            events.emit(Event::success(
+                "Every mapping and included file matches the archive",
+            ));
+            Ok(())
+        } else {
+            Err(HermioneError::Integrity(format!(
+                "{} does not match its manifest: {}",
+                self.package_path.display(),
+                failures.join(", ")
+            ))
+            .into())
+        }
+    }
+}
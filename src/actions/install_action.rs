@@ -1,23 +1,33 @@
 use color_eyre::eyre::Result;
-use paris::Logger;
+
+use hermione::conflict_strategy::ConflictStrategy;
+use hermione::events::Event;
+use hermione::output::PackageSummary;
+use hermione::package_service::PackageService;
 
 use crate::action::Action;
-use crate::package_service::PackageService;
 
 /// Install Action installs a given Hermione package.
 pub struct InstallAction {
     pub package_source: String,
+    pub on_conflict: Option<ConflictStrategy>,
 }
 
 impl Action for InstallAction {
     fn execute(self, package_service: PackageService) -> Result<()> {
-        let mut logger = Logger::new();
-        logger.info("Initialized").info(format!(
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        events.emit(Event::info(format!(
             "Downloading and installing from {}",
             &self.package_source
-        ));
-        package_service.download_and_install(self.package_source)?;
-        logger.success("Done.");
-        Ok(())
+        )));
+        let package_service = PackageService {
+            on_conflict: self.on_conflict,
+            ..package_service
+        };
+        let output = package_service.output.clone();
+        let installed_package = package_service.download_and_install(self.package_source)?;
+        events.emit(Event::success("Done."));
+        output.set("package", PackageSummary::from(&installed_package))
     }
 }
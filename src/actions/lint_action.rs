@@ -0,0 +1,42 @@
+use color_eyre::eyre::Result;
+
+use hermione::error::HermioneError;
+use hermione::events::Event;
+use hermione::lint;
+use hermione::package_service::PackageService;
+
+use std::path::PathBuf;
+
+use crate::action::Action;
+
+/// Lint Action reports every problem in a package directory at once.
+pub struct LintAction {
+    pub package_path: PathBuf,
+}
+
+impl Action for LintAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let problems = lint::lint(&package_service, &self.package_path)?;
+        for problem in &problems {
+            events.emit(Event::error(problem.to_string()));
+        }
+        package_service.output.set("problems", &problems)?;
+
+        if problems.is_empty() {
+            events.emit(Event::success(format!(
+                "No problems found in {}",
+                self.package_path.display()
+            )));
+            Ok(())
+        } else {
+            Err(HermioneError::Manifest(format!(
+                "Found {} problem(s) in {}",
+                problems.len(),
+                self.package_path.display()
+            ))
+            .into())
+        }
+    }
+}
@@ -1,29 +1,51 @@
 use color_eyre::eyre::Result;
-use paris::Logger;
+
+use hermione::events::Event;
+use hermione::output::PackageSummary;
+use hermione::package_service::PackageService;
+use hermione::pins::Pins;
 
 use crate::action::Action;
-use crate::package_service::PackageService;
 
 /// List Action displays a list of all currently installed Hermione Packages
 pub struct ListAction {}
 
 impl Action for ListAction {
     fn execute(self, package_service: PackageService) -> Result<()> {
-        let mut logger = Logger::new();
-        logger.info("Initialized");
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
         let installed_packages = package_service.list_installed_packages()?;
+        let pins = Pins::load(&package_service)?;
         installed_packages
             .iter()
             .enumerate()
             .for_each(|(index, installed_package)| {
-                logger.indent(1).info(format!(
-                    "{}. {} @ {}",
-                    (index + 1).to_string(),
+                let pin = match pins.get(&installed_package.manifest.id) {
+                    Some(version_req) => format!(" <yellow>pinned {}</>", version_req),
+                    None => String::new(),
+                };
+                events.emit(Event::detail(format!(
+                    "{}. {} @ {}{}",
+                    (index + 1),
                     installed_package.manifest.id,
-                    installed_package.manifest.version
-                ));
+                    installed_package.manifest.version,
+                    pin
+                )));
             });
-        logger.success(format!("Displayed: {} Packages", installed_packages.len(),));
+        events.emit(Event::success(format!(
+            "Displayed: {} Packages",
+            installed_packages.len(),
+        )));
+        package_service.output.set(
+            "packages",
+            installed_packages
+                .iter()
+                .map(|installed_package| {
+                    PackageSummary::from(installed_package)
+                        .with_pin(pins.get(&installed_package.manifest.id).cloned())
+                })
+                .collect::<Vec<_>>(),
+        )?;
         Ok(())
     }
 }
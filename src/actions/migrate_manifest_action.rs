@@ -0,0 +1,54 @@
+use color_eyre::eyre::{Result, WrapErr};
+
+use hermione::events::Event;
+use hermione::manifest::{Manifest, ManifestFormat};
+use hermione::migration;
+use hermione::package_service::PackageService;
+use hermione::plan::PlannedOperation;
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::action::Action;
+
+/// Migrate Manifest Action upgrades the manifest of a package directory to the current format in place.
+pub struct MigrateManifestAction {
+    pub package_path: PathBuf,
+}
+
+impl Action for MigrateManifestAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let manifest_path = Manifest::path_in(&self.package_path)?;
+        let contents = fs::read_to_string(&manifest_path)
+            .wrap_err_with(|| format!("Unable to read {}", manifest_path.display()))?;
+        let migration = migration::migrate(&contents, ManifestFormat::from_path(&manifest_path))?;
+
+        if migration.is_current() {
+            events.emit(Event::success(format!(
+                "{} is already in manifest format {}",
+                manifest_path.display(),
+                migration.to
+            )));
+        } else {
+            package_service.perform(
+                PlannedOperation::Write {
+                    path: manifest_path.clone(),
+                },
+                || {
+                    fs::write(&manifest_path, &migration.contents)
+                        .wrap_err_with(|| format!("Unable to write {}", manifest_path.display()))
+                },
+            )?;
+            events.emit(Event::success(format!(
+                "Migrated {} from manifest format {} to {}",
+                manifest_path.display(),
+                migration.from,
+                migration.to
+            )));
+        }
+        package_service.output.set("path", &manifest_path)?;
+        package_service.output.set("migration", migration)
+    }
+}
@@ -1,8 +1,27 @@
+pub mod adopt_action;
+pub mod backup_list_action;
+pub mod backup_restore_action;
+pub mod export_action;
+pub mod history_action;
 pub mod implode_action;
+pub mod info_action;
 pub mod init_action;
+pub mod inspect_action;
 pub mod install_action;
+pub mod lint_action;
 pub mod list_action;
+pub mod migrate_manifest_action;
 pub mod new_action;
+pub mod outdated_action;
 pub mod package_action;
+pub mod pin_action;
 pub mod remove_action;
+pub mod repair_action;
+pub mod rollback_action;
+pub mod schema_action;
+pub mod search_action;
+pub mod sync_action;
+pub mod undo_action;
+pub mod unpin_action;
 pub mod upgrade_action;
+pub mod verify_action;
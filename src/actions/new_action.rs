@@ -1,9 +1,10 @@
 use color_eyre::eyre::Result;
-use paris::Logger;
+
+use hermione::events::Event;
+use hermione::package_service::PackageService;
+use hermione::scaffold::Scaffold;
 
 use crate::action::Action;
-use crate::package_service::PackageService;
-use crate::scaffold::Scaffold;
 
 /// New Action scaffolds a Hermione package directory with a `hermione.yml` file and a couple of sample files.
 pub struct NewAction {
@@ -12,10 +13,10 @@ pub struct NewAction {
 }
 
 impl Action for NewAction {
-    fn execute(self, _package_service: PackageService) -> Result<()> {
-        let mut logger = Logger::new();
-        logger.info("Initialized");
-        let scaffold = Scaffold::new(&self.package_name, &self.package_id);
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let scaffold = Scaffold::new(&self.package_name, &self.package_id).with_events(events);
         scaffold.create_package()
     }
 }
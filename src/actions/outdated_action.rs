@@ -0,0 +1,41 @@
+use color_eyre::eyre::Result;
+
+use hermione::events::Event;
+use hermione::outdated;
+use hermione::package_service::PackageService;
+use hermione::HermioneError;
+
+use crate::action::Action;
+
+/// Outdated Action reports installed packages with newer versions available
+/// and fails when there are any, so CI can flag stale machines.
+pub struct OutdatedAction {}
+
+impl Action for OutdatedAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let packages = outdated::check(&package_service)?;
+        for package in &packages {
+            events.emit(Event::detail(package.to_string()));
+        }
+        let outdated = packages
+            .iter()
+            .filter(|package| package.outdated)
+            .map(|package| package.id.clone())
+            .collect::<Vec<_>>();
+        package_service.output.set("packages", &packages)?;
+
+        if outdated.is_empty() {
+            events.emit(Event::success("Everything is up to date"));
+            Ok(())
+        } else {
+            Err(HermioneError::Outdated(format!(
+                "{} package(s) outdated: {}",
+                outdated.len(),
+                outdated.join(", ")
+            ))
+            .into())
+        }
+    }
+}
@@ -1,25 +1,43 @@
 use color_eyre::eyre::Result;
-use paris::Logger;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use hermione::events::Event;
+use hermione::package_service::PackageService;
+use hermione::packer::Packer;
 
 use crate::action::Action;
-use crate::package_service::PackageService;
-use crate::packer::Packer;
 
-/// List Action displays a list of all currently installed Hermione Packages
+/// Package Action packs a package directory into a `.hpkg` archive.
 pub struct PackageAction {
     pub package_path: String,
+    /// Whether to pack a reproducible archive, see `Packer::with_deterministic`.
+    pub deterministic: bool,
+    /// Whether to write the computed integrity to the source manifest as well.
+    pub write_integrity: bool,
+    /// Directory to write the archive to.
+    pub out_dir: Option<PathBuf>,
+    /// Path to write the archive to.
+    pub out: Option<PathBuf>,
 }
 
 impl Action for PackageAction {
-    fn execute(self, _package_service: PackageService) -> Result<()> {
-        let mut logger = Logger::new();
-        logger.info("Initialized");
-        match Packer::new(Path::new(&self.package_path).to_path_buf()).pack() {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let packer = Packer::new(Path::new(&self.package_path).to_path_buf())
+            .with_events(events.clone())
+            .with_deterministic(self.deterministic)
+            .with_write_integrity(self.write_integrity)
+            .with_out_dir(self.out_dir)
+            .with_out(self.out);
+        match packer.pack() {
             Ok(archive_location) => {
-                logger.info(format!("Archive Created at Path: {}", archive_location));
-                Ok(())
+                events.emit(Event::info(format!(
+                    "Archive Created at Path: {}",
+                    archive_location
+                )));
+                package_service.output.set("archive", archive_location)
             }
             Err(e) => Err(e),
         }
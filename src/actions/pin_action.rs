@@ -0,0 +1,38 @@
+use color_eyre::eyre::Result;
+
+use hermione::events::Event;
+use hermione::package_service::PackageService;
+use hermione::pins::Pins;
+
+use crate::action::Action;
+
+/// Pin Action holds an installed package back from upgrades.
+pub struct PinAction {
+    pub package_id: String,
+    /// Requirement upgrades have to meet, defaults to the installed version.
+    pub version_req: Option<String>,
+}
+
+impl Action for PinAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let installed_package = package_service
+            .clone()
+            .get_installed_package(self.package_id.clone())?;
+        let version_req = self
+            .version_req
+            .unwrap_or_else(|| format!("={}", installed_package.manifest.version));
+
+        let mut pins = Pins::load(&package_service)?;
+        pins.pin(&self.package_id, &version_req)?;
+        pins.store(&package_service)?;
+
+        events.emit(Event::success(format!(
+            "Pinned {} to {}",
+            self.package_id, version_req
+        )));
+        package_service.output.set("package", &self.package_id)?;
+        package_service.output.set("pin", version_req)
+    }
+}
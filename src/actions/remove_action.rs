@@ -1,8 +1,9 @@
 use color_eyre::eyre::Result;
-use paris::Logger;
+
+use hermione::events::Event;
+use hermione::package_service::PackageService;
 
 use crate::action::Action;
-use crate::package_service::PackageService;
 
 /// Remove Action removes a currently installed Hermione Package.
 pub struct RemoveAction {
@@ -11,17 +12,25 @@ pub struct RemoveAction {
 
 impl Action for RemoveAction {
     fn execute(self, package_service: PackageService) -> Result<()> {
-        let mut logger = Logger::new();
-        logger.info("Initialized");
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let output = package_service.output.clone();
+        output.set("package", &self.package_name)?;
         let remove_result = match package_service.get_installed_package(self.package_name) {
             Ok(package) => package.remove(),
             Err(e) => Err(e),
         };
 
+        output.set("removed", remove_result.is_ok())?;
         match remove_result {
-            Ok(_success) => logger.success("Removal successful"),
-            Err(e) => logger.error(format!("Unable to remove because: {}", e.to_string())),
-        };
-        Ok(())
+            Ok(_success) => {
+                events.emit(Event::success("Removal successful"));
+                Ok(())
+            }
+            Err(e) => {
+                events.emit(Event::error(format!("Unable to remove because: {}", e)));
+                Err(e)
+            }
+        }
     }
 }
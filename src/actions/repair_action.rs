@@ -0,0 +1,43 @@
+use color_eyre::eyre::Result;
+
+use hermione::events::Event;
+use hermione::package_service::PackageService;
+
+use crate::action::Action;
+
+/// Repair Action restores drifted or missing mappings of installed packages.
+pub struct RepairAction {
+    pub package_names: Vec<String>,
+    pub run_hooks: bool,
+}
+
+impl Action for RepairAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let packages_to_repair = if self.package_names.is_empty() {
+            package_service.list_installed_packages()?
+        } else {
+            self.package_names
+                .iter()
+                .map(|package_name| {
+                    package_service
+                        .clone()
+                        .get_installed_package(String::from(package_name))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        let mut repaired = 0;
+        for installed_package in packages_to_repair {
+            events.emit(Event::info(format!(
+                "Repairing {} @ {}",
+                installed_package.manifest.id, installed_package.manifest.version
+            )));
+            repaired += installed_package.repair(self.run_hooks)?;
+        }
+
+        events.emit(Event::success(format!("Repaired: {} Mappings", repaired)));
+        package_service.output.set("repaired", repaired)
+    }
+}
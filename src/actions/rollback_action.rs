@@ -0,0 +1,29 @@
+use color_eyre::eyre::Result;
+
+use hermione::events::Event;
+use hermione::output::PackageSummary;
+use hermione::package_service::PackageService;
+
+use crate::action::Action;
+
+/// Rollback Action switches an installed package back to a kept version.
+pub struct RollbackAction {
+    pub package_id: String,
+    /// Version to go back to, defaults to the newest kept version older than the current one.
+    pub version: Option<String>,
+}
+
+impl Action for RollbackAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let output = package_service.output.clone();
+        let installed_package = package_service.get_installed_package(self.package_id)?;
+        let rolled_back = installed_package.rollback(self.version)?;
+        events.emit(Event::success(format!(
+            "{} is now at {}",
+            rolled_back.manifest.id, rolled_back.manifest.version
+        )));
+        output.set("package", PackageSummary::from(&rolled_back))
+    }
+}
@@ -0,0 +1,25 @@
+use color_eyre::eyre::Result;
+
+use hermione::package_service::PackageService;
+use hermione::Manifest;
+
+use crate::action::Action;
+
+/// Schema Action prints the JSON Schema of `hermione.yml` for editors to use.
+pub struct SchemaAction {}
+
+impl Action for SchemaAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let schema = Manifest::json_schema()?;
+        if package_service.output.is_json() {
+            package_service.output.set(
+                "schema",
+                serde_json::from_str::<serde_json::Value>(&schema)?,
+            )
+        } else {
+            // Only the schema goes to stdout so it can be redirected into a file
+            println!("{}", schema);
+            Ok(())
+        }
+    }
+}
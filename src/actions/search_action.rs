@@ -0,0 +1,25 @@
+use color_eyre::eyre::Result;
+
+use hermione::events::Event;
+use hermione::package_service::PackageService;
+use hermione::search;
+
+use crate::action::Action;
+
+/// Search Action lists packages of the configured repositories matching a query.
+pub struct SearchAction {
+    pub query: String,
+}
+
+impl Action for SearchAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let results = search::search(&package_service, &self.query)?;
+        for result in &results {
+            events.emit(Event::detail(result.to_string()));
+        }
+        events.emit(Event::info(format!("Found: {}", results.len())));
+        package_service.output.set("packages", &results)
+    }
+}
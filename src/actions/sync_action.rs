@@ -0,0 +1,46 @@
+use color_eyre::eyre::Result;
+
+use hermione::conflict_strategy::ConflictStrategy;
+use hermione::events::Event;
+use hermione::hermfile::{Hermfile, SyncStep};
+use hermione::package_service::PackageService;
+
+use std::path::PathBuf;
+
+use crate::action::Action;
+
+/// Sync Action brings the installed packages in line with a Hermfile.
+pub struct SyncAction {
+    pub hermfile_path: PathBuf,
+    pub prune: bool,
+    pub on_conflict: Option<ConflictStrategy>,
+}
+
+impl Action for SyncAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let package_service = PackageService {
+            on_conflict: self.on_conflict,
+            ..package_service
+        };
+        let hermfile = Hermfile::new_from_path(&self.hermfile_path)?;
+        let steps = hermfile.plan(&package_service, self.prune)?;
+
+        if steps.iter().all(SyncStep::is_noop) {
+            events.emit(Event::info("Already in sync, nothing to do"));
+        } else {
+            events.emit(Event::info("Sync plan:"));
+        }
+        for step in &steps {
+            events.emit(Event::detail(step.to_string()));
+        }
+
+        for step in steps.iter().filter(|step| !step.is_noop()) {
+            step.apply(&package_service)?;
+        }
+
+        events.emit(Event::success("Done."));
+        package_service.output.set("steps", steps)
+    }
+}
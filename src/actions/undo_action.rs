@@ -0,0 +1,23 @@
+use color_eyre::eyre::Result;
+
+use hermione::events::Event;
+use hermione::history::History;
+use hermione::package_service::PackageService;
+
+use crate::action::Action;
+
+/// Undo Action reverts the most recent operation which has not been undone yet.
+pub struct UndoAction {}
+
+impl Action for UndoAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let undone = History::new(&package_service).undo()?;
+        events.emit(Event::success(format!(
+            "Undid {} of {}",
+            undone.operation, undone.package
+        )));
+        package_service.output.set("undone", undone)
+    }
+}
@@ -0,0 +1,35 @@
+use color_eyre::eyre::Result;
+
+use hermione::events::Event;
+use hermione::package_service::PackageService;
+use hermione::pins::Pins;
+
+use crate::action::Action;
+
+/// Unpin Action lets a pinned package be upgraded again.
+pub struct UnpinAction {
+    pub package_id: String,
+}
+
+impl Action for UnpinAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let mut pins = Pins::load(&package_service)?;
+        let unpinned = pins.unpin(&self.package_id);
+        match &unpinned {
+            Some(version_req) => {
+                pins.store(&package_service)?;
+                events.emit(Event::success(format!(
+                    "Unpinned {} from {}",
+                    self.package_id, version_req
+                )));
+            }
+            None => {
+                events.emit(Event::warning(format!("{} is not pinned", self.package_id)));
+            }
+        }
+        package_service.output.set("package", &self.package_id)?;
+        package_service.output.set("unpinned", unpinned.is_some())
+    }
+}
@@ -1,19 +1,34 @@
 use color_eyre::eyre::Result;
-use paris::Logger;
+use semver::Version;
+
+use hermione::conflict_strategy::ConflictStrategy;
+use hermione::error::HermioneError;
+use hermione::events::Event;
+use hermione::history::{History, HistoryEntry, HistoryOperation};
+use hermione::manifest::Manifest;
+use hermione::output::PackageSummary;
+use hermione::package_service::PackageService;
+use hermione::pins::Pins;
+use hermione::receipt::Receipt;
 
 use crate::action::Action;
-use crate::package_service::PackageService;
 
-/// Upgrade Action upgrades a package
+/// Upgrade Action upgrades packages to what their recorded source offers now, when that is newer.
+/// Pinned packages are only upgraded to versions meeting their pin.
 pub struct UpgradeAction {
     pub package_names: Vec<String>,
+    pub on_conflict: Option<ConflictStrategy>,
 }
 
 impl Action for UpgradeAction {
     fn execute(self, package_service: PackageService) -> Result<()> {
-        let mut logger = Logger::new();
+        let events = package_service.events.clone();
+        let package_service = PackageService {
+            on_conflict: self.on_conflict,
+            ..package_service
+        };
         let packages_to_upgrade = if self.package_names.is_empty() {
-            logger.info("No packages given, defaulting to all of them");
+            events.emit(Event::info("No packages given, defaulting to all of them"));
             package_service.list_installed_packages()?
         } else {
             self.package_names
@@ -23,16 +38,84 @@ impl Action for UpgradeAction {
                         .clone()
                         .get_installed_package(String::from(package_name))
                 })
-                .filter(|res| res.is_ok())
-                .map(|res| res.expect("Unable to unwrap an InstalledPackage"))
+                .filter_map(|res| res.ok())
                 .collect()
         };
 
+        let pins = Pins::load(&package_service)?;
+        let mut upgraded = Vec::new();
+        let history = History::new(&package_service);
         for installed_package in packages_to_upgrade {
-            let downloaded_package = installed_package.uninstall()?;
-            downloaded_package.upgrade()?;
+            let id = installed_package.manifest.id.clone();
+            let receipt = match Receipt::load(&installed_package.local_path) {
+                Ok(receipt) => receipt,
+                Err(_) => {
+                    let error = HermioneError::Policy(format!(
+                        "{} has no recorded source; reinstall it to enable upgrades",
+                        id
+                    ));
+                    if !self.package_names.is_empty() {
+                        return Err(error.into());
+                    }
+                    events.emit(Event::warning(error.to_string()));
+                    continue;
+                }
+            };
+            let (candidate, manifest) = match package_service
+                .clone()
+                .download(receipt.source.clone())
+                .and_then(|candidate| {
+                    let manifest = Manifest::new_from_dir(&candidate.local_path)?;
+                    Ok((candidate, manifest))
+                }) {
+                Ok(downloaded) => downloaded,
+                Err(e) => {
+                    events.emit(Event::warning(format!(
+                        "Unable to check the source of {}: {:#}",
+                        id, e
+                    )));
+                    continue;
+                }
+            };
+            let newer = Version::parse(&manifest.version).and_then(|offered| {
+                Ok(offered > Version::parse(&installed_package.manifest.version)?)
+            });
+            match newer {
+                Ok(true) => {}
+                Ok(false) => {
+                    events.emit(Event::info(format!(
+                        "{} is up to date at {}, its source offers {}",
+                        id, installed_package.manifest.version, manifest.version
+                    )));
+                    continue;
+                }
+                Err(e) => {
+                    events.emit(Event::warning(format!(
+                        "Unable to compare {} {} with {}: {}",
+                        id, installed_package.manifest.version, manifest.version, e
+                    )));
+                    continue;
+                }
+            }
+            if let Some(version_req) = pins.get(&id) {
+                if !pins.allows(&id, &manifest.version)? {
+                    events.emit(Event::warning(format!(
+                        "Holding {} at {} because {} is not allowed by its pin {}, see 'herm unpin'",
+                        id, installed_package.manifest.version, manifest.version, version_req
+                    )));
+                    continue;
+                }
+            }
+            let entry =
+                HistoryEntry::new(HistoryOperation::Upgrade, &id).before(&installed_package);
+            let upgraded_package = history.track(entry, |entry| {
+                let upgraded_package = installed_package.replace_with(candidate)?;
+                let entry = entry.after(&upgraded_package);
+                Ok((upgraded_package, entry))
+            })?;
+            upgraded.push(PackageSummary::from(&upgraded_package));
         }
 
-        Ok(())
+        package_service.output.set("packages", upgraded)
     }
 }
@@ -0,0 +1,66 @@
+use color_eyre::eyre::Result;
+use serde_json::json;
+
+use hermione::error::HermioneError;
+use hermione::events::Event;
+use hermione::output::{MappingSummary, PackageSummary};
+use hermione::package_service::PackageService;
+
+use crate::action::Action;
+
+/// Verify Action reports installed mappings which no longer match what was installed.
+pub struct VerifyAction {
+    pub package_names: Vec<String>,
+}
+
+impl Action for VerifyAction {
+    fn execute(self, package_service: PackageService) -> Result<()> {
+        let events = package_service.events.clone();
+        events.emit(Event::info("Initialized"));
+        let packages_to_verify = if self.package_names.is_empty() {
+            package_service.list_installed_packages()?
+        } else {
+            self.package_names
+                .iter()
+                .map(|package_name| {
+                    package_service
+                        .clone()
+                        .get_installed_package(String::from(package_name))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        let mut drifted = 0;
+        let mut verified = Vec::new();
+        for installed_package in packages_to_verify {
+            events.emit(Event::info(format!(
+                "{} @ {}",
+                installed_package.manifest.id, installed_package.manifest.version
+            )));
+            let checks = installed_package.verify()?;
+            for check in &checks {
+                if check.state.is_drift() {
+                    drifted += 1;
+                }
+                events.emit(Event::detail(check.display_line()));
+            }
+            verified.push(json!({
+                "package": PackageSummary::from(&installed_package),
+                "mappings": checks.iter().map(MappingSummary::from).collect::<Vec<_>>(),
+            }));
+        }
+        package_service.output.set("packages", verified)?;
+        package_service.output.set("drifted", drifted)?;
+
+        if drifted == 0 {
+            events.emit(Event::success("No drift detected"));
+            Ok(())
+        } else {
+            events.emit(Event::error(format!(
+                "Drift detected in {} mapping(s)",
+                drifted
+            )));
+            Err(HermioneError::Drift(format!("Drift detected in {} mapping(s)", drifted)).into())
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use color_eyre::eyre::{eyre, Result, WrapErr};
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::events::Event;
+use crate::file_mapping_definition::FileMappingDefinition;
+use crate::manifest::Manifest;
+use crate::package_service::PackageService;
+
+/// Adopter is responsible for pulling existing files on the system into a Hermione package.
+pub struct Adopter {
+    pub package_path_buf: PathBuf,
+}
+
+/// A file which has been moved into a package by the Adopter.
+pub struct AdoptedFile {
+    /// Where the file used to live.
+    pub original_path: PathBuf,
+    /// Where the file lives now inside the package.
+    pub package_file_path: PathBuf,
+}
+
+impl Adopter {
+    pub fn new(package_path_buf: PathBuf) -> Self {
+        Self { package_path_buf }
+    }
+
+    /// Moves every given file into the package and adds a mapping for it to the manifest.
+    /// Output paths are templatized against the facts known to Hermione (e.g. `{{HOME}}`).
+    ///
+    /// ### Arguments
+    ///
+    /// * package_service - Borrowed reference to PackageService.
+    /// * paths - Files to adopt.
+    ///
+    /// Returns the adopted files as a Result.
+    pub fn adopt(
+        &self,
+        package_service: &PackageService,
+        paths: Vec<PathBuf>,
+    ) -> Result<Vec<AdoptedFile>> {
+        let manifest_path = Manifest::path_in(&self.package_path_buf).wrap_err_with(|| {
+            format!(
+                "Unable to adopt into {}. Try 'herm init' or 'herm new' first.",
+                self.package_path_buf.display()
+            )
+        })?;
+        let manifest = Manifest::new_from_path(manifest_path.clone()).wrap_err_with(|| {
+            format!(
+                "Unable to adopt into {}. Try 'herm init' or 'herm new' first.",
+                self.package_path_buf.display()
+            )
+        })?;
+        let facts = package_service
+            .facts()?
+            .into_iter()
+            .map(|(name, fact_path)| (name, fs::canonicalize(&fact_path).unwrap_or(fact_path)))
+            .collect::<Vec<_>>();
+
+        let mut new_mappings: Vec<FileMappingDefinition> = Vec::new();
+        let mut plans = Vec::new();
+        for path in paths {
+            let original_path = absolute_path(&path)?;
+            let metadata = fs::symlink_metadata(&original_path)
+                .wrap_err_with(|| format!("Unable to read {}", original_path.display()))?;
+            if !metadata.file_type().is_file() {
+                return Err(eyre!(
+                    "Only regular files can be adopted, {} is not one",
+                    original_path.display()
+                ));
+            }
+
+            let (o, i) = templatize(&original_path, &facts);
+            let i = i.to_string_lossy().replace('\\', "/");
+            if manifest
+                .mappings
+                .iter()
+                .chain(new_mappings.iter())
+                .any(|mapping| mapping.i == i || mapping.o == o)
+            {
+                return Err(eyre!(
+                    "Package {} already has a mapping for {}",
+                    manifest.id,
+                    original_path.display()
+                ));
+            }
+            if self.package_path_buf.join(&i).exists() {
+                return Err(eyre!(
+                    "{} already exists in package {}",
+                    i,
+                    self.package_path_buf.display()
+                ));
+            }
+
+            new_mappings.push(FileMappingDefinition::new(i.clone(), o, None, None, None));
+            plans.push((original_path, i));
+        }
+
+        let mut adopted = Vec::new();
+        for (original_path, i) in plans {
+            let package_file_path = self.package_path_buf.join(&i);
+            if let Some(parent_path) = package_file_path.parent() {
+                fs::create_dir_all(parent_path)?;
+            }
+            let move_result = fs::rename(&original_path, &package_file_path).or_else(|_| {
+                fs::copy(&original_path, &package_file_path)
+                    .and_then(|_| fs::remove_file(&original_path))
+            });
+            if let Err(e) = move_result {
+                Self::revert(adopted)?;
+                return Err(eyre!(
+                    "Unable to move {} into package: {}",
+                    original_path.display(),
+                    e
+                ));
+            }
+            package_service.events.emit(Event::detail(format!(
+                "<blue>Adopted</> {} <blue>-></> {}",
+                original_path.display(),
+                package_file_path.display()
+            )));
+            adopted.push(AdoptedFile {
+                original_path,
+                package_file_path,
+            });
+        }
+
+        let mut mappings = manifest.mappings.clone();
+        for mapping in new_mappings {
+            mappings.push(mapping.with_integrity_set(self.package_path_buf.clone())?);
+        }
+        manifest.set_mappings(mappings).store(&manifest_path)?;
+        package_service.events.emit(Event::info(format!(
+            "Wrote mappings to {}",
+            manifest_path.display()
+        )));
+
+        Ok(adopted)
+    }
+
+    /// Moves adopted files back to where they were found.
+    pub fn revert(adopted: Vec<AdoptedFile>) -> Result<()> {
+        for adopted_file in adopted {
+            if adopted_file.original_path.exists() {
+                fs::remove_file(&adopted_file.original_path)?;
+            }
+            fs::rename(&adopted_file.package_file_path, &adopted_file.original_path)
+                .or_else(|_| {
+                    fs::copy(&adopted_file.package_file_path, &adopted_file.original_path)
+                        .map(|_| ())
+                })
+                .wrap_err_with(|| {
+                    format!(
+                        "Unable to move {} back to {}",
+                        adopted_file.package_file_path.display(),
+                        adopted_file.original_path.display()
+                    )
+                })?;
+        }
+        Ok(())
+    }
+}
+
+/// Resolves a path against the current directory without following a final symlink.
+fn absolute_path(path: &Path) -> Result<PathBuf> {
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        env::current_dir()?.join(path)
+    };
+    match (joined.parent(), joined.file_name()) {
+        (Some(parent), Some(file_name)) => Ok(fs::canonicalize(parent)?.join(file_name)),
+        _ => Err(eyre!("Unable to adopt {}", path.display())),
+    }
+}
+
+/// Returns the templatized output path and the path of the file inside the package.
+/// The most specific fact which contains the path wins.
+///
+/// ### Arguments
+///
+/// * path - Absolute path of the file being adopted.
+/// * facts - Pairs of template variable name and the directory it stands for.
+fn templatize(path: &Path, facts: &[(String, PathBuf)]) -> (String, PathBuf) {
+    let best_fact = facts
+        .iter()
+        .filter_map(|(name, fact_path)| {
+            path.strip_prefix(fact_path)
+                .ok()
+                .map(|relative| (name, fact_path, relative))
+        })
+        .max_by_key(|(_, fact_path, _)| fact_path.components().count());
+
+    match best_fact {
+        Some((name, _, relative)) => (
+            format!(
+                "{{{{{}}}}}/{}",
+                name,
+                relative.to_string_lossy().replace('\\', "/")
+            ),
+            relative.to_path_buf(),
+        ),
+        None => (
+            path.to_string_lossy().to_string(),
+            path.file_name()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| path.to_path_buf()),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facts() -> Vec<(String, PathBuf)> {
+        vec![
+            (String::from("HOME"), PathBuf::from("/home/hermione")),
+            (
+                String::from("XDG_CONFIG_HOME"),
+                PathBuf::from("/home/hermione/.config"),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_templatize_prefers_most_specific_fact() {
+        let (o, i) = templatize(Path::new("/home/hermione/.config/nvim/init.vim"), &facts());
+        assert_eq!("{{XDG_CONFIG_HOME}}/nvim/init.vim", o);
+        assert_eq!(PathBuf::from("nvim/init.vim"), i);
+    }
+
+    #[test]
+    fn test_templatize_home() {
+        let (o, i) = templatize(Path::new("/home/hermione/.bashrc"), &facts());
+        assert_eq!("{{HOME}}/.bashrc", o);
+        assert_eq!(PathBuf::from(".bashrc"), i);
+    }
+
+    #[test]
+    fn test_templatize_without_fact() {
+        let (o, i) = templatize(Path::new("/etc/hosts"), &facts());
+        assert_eq!("/etc/hosts", o);
+        assert_eq!(PathBuf::from("hosts"), i);
+    }
+}
@@ -0,0 +1,197 @@
+use chrono::Local;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use fs_extra::dir;
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::HermioneError;
+
+const BACKUP_FILE_NAME: &str = "backup.yml";
+
+/// A file which existed before Hermione installed over it.
+/// Each backup lives in its own timestamped directory inside the backup area
+/// next to a `backup.yml` describing where it came from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Backup {
+    /// Unique identifier, derived from the time the backup was taken.
+    pub id: String,
+    /// Id of the package whose install displaced the file.
+    pub package_id: String,
+    /// Where the file lived before it was backed up.
+    pub original_path: PathBuf,
+    /// Where the file lives inside the backup area.
+    pub backup_path: PathBuf,
+    /// RFC 3339 timestamp of when the backup was taken.
+    pub created_at: String,
+}
+
+impl Backup {
+    /// Moves the file at `original_path` into a new backup directory.
+    ///
+    /// ### Arguments
+    ///
+    /// * backup_dir - Root of the Hermione backup area.
+    /// * package_id - Id of the package being installed.
+    /// * original_path - Path of the pre-existing file.
+    ///
+    /// Returns the recorded Backup as a Result.
+    pub fn create(backup_dir: &Path, package_id: &str, original_path: &Path) -> Result<Self> {
+        let now = Local::now();
+        let stamp = now.format("%Y%m%d%H%M%S").to_string();
+        let mut sequence = 1;
+        let mut id = format!("{}-{}", stamp, sequence);
+        while backup_dir.join(&id).exists() {
+            sequence += 1;
+            id = format!("{}-{}", stamp, sequence);
+        }
+
+        let location = backup_dir.join(&id);
+        fs::create_dir_all(&location).wrap_err_with(|| {
+            format!("Unable to create backup directory {}", location.display())
+        })?;
+
+        let file_name = original_path
+            .file_name()
+            .ok_or_else(|| eyre!("Unable to back up {}", original_path.display()))?;
+        let backup_path = location.join(file_name);
+        move_path(original_path, &backup_path)?;
+
+        let backup = Self {
+            id,
+            package_id: String::from(package_id),
+            original_path: original_path.to_path_buf(),
+            backup_path,
+            created_at: now.to_rfc3339(),
+        };
+        let yaml = serde_yaml::to_string(&backup)?;
+        fs::write(location.join(BACKUP_FILE_NAME), yaml)?;
+
+        Ok(backup)
+    }
+
+    /// Returns every backup found in the backup area, oldest first.
+    pub fn list(backup_dir: &Path) -> Result<Vec<Self>> {
+        if !backup_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = fs::read_dir(backup_dir)?
+            .map(|entry_result| entry_result.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, std::io::Error>>()?;
+        entries.sort();
+
+        let backups = entries
+            .iter()
+            .map(|entry| entry.join(BACKUP_FILE_NAME))
+            .filter(|metadata_path| metadata_path.is_file())
+            .filter_map(|metadata_path| {
+                fs::read_to_string(metadata_path)
+                    .ok()
+                    .and_then(|yaml| serde_yaml::from_str(&yaml).ok())
+            })
+            .collect();
+        Ok(backups)
+    }
+
+    /// Returns the backups taken while installing the given package.
+    pub fn list_for_package(backup_dir: &Path, package_id: &str) -> Result<Vec<Self>> {
+        Ok(Self::list(backup_dir)?
+            .into_iter()
+            .filter(|backup| backup.package_id == package_id)
+            .collect())
+    }
+
+    /// Finds a backup by id.
+    pub fn find(backup_dir: &Path, id: &str) -> Result<Self> {
+        Self::list(backup_dir)?
+            .into_iter()
+            .find(|backup| backup.id == id)
+            .ok_or_else(|| eyre!("No backup with id {}", id))
+    }
+
+    /// Moves the backed up file back to its original location and
+    /// removes it from the backup area.
+    ///
+    /// Returns String as a Result.
+    pub fn restore(self) -> Result<String> {
+        if self.original_path.exists() {
+            return Err(HermioneError::Conflict(format!(
+                "Unable to restore backup {} because {} exists and Hermione will not overwrite it.",
+                self.id,
+                self.original_path.display()
+            ))
+            .into());
+        }
+        if let Some(parent_path) = self.original_path.parent() {
+            if !parent_path.exists() {
+                fs::create_dir_all(parent_path)?;
+            }
+        }
+        move_path(&self.backup_path, &self.original_path)?;
+        if let Some(location) = self.backup_path.parent() {
+            fs::remove_dir_all(location)?;
+        }
+
+        Ok(format!(
+            "<green>Restored</> {} from backup {}",
+            self.original_path.display(),
+            self.id
+        ))
+    }
+}
+
+/// Moves a file or directory, falling back to copy and delete when
+/// a rename is not possible (e.g. across filesystems).
+fn move_path(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    if from.is_dir() {
+        let mut options = dir::CopyOptions::new();
+        options.copy_inside = true;
+        dir::move_dir(from, to, &options)
+            .map(|_| ())
+            .map_err(|e| eyre!("Unable to move {}: {}", from.display(), e))
+    } else {
+        fs::copy(from, to)
+            .wrap_err_with(|| format!("Unable to move {} -> {}", from.display(), to.display()))?;
+        fs::remove_file(from)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_create_and_restore() {
+        let backup_dir = TempDir::new().expect("Unable to create backup dir in test");
+        let home_dir = TempDir::new().expect("Unable to create home dir in test");
+        let original_path = home_dir.path().join("panda.txt");
+        fs::write(&original_path, "bamboo").expect("Unable to write file in test");
+
+        let backup = Backup::create(backup_dir.path(), "org.example.test", &original_path)
+            .expect("Unable to create backup in test");
+        assert!(!original_path.exists());
+        assert!(backup.backup_path.is_file());
+
+        let listed = Backup::list_for_package(backup_dir.path(), "org.example.test")
+            .expect("Unable to list backups in test");
+        assert_eq!(vec![backup.clone()], listed);
+
+        backup.restore().expect("Unable to restore backup in test");
+        assert_eq!(
+            "bamboo",
+            fs::read_to_string(&original_path).expect("Unable to read file in test")
+        );
+        assert!(Backup::list(backup_dir.path())
+            .expect("Unable to list backups in test")
+            .is_empty());
+    }
+}
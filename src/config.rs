@@ -1,9 +1,10 @@
 use color_eyre::eyre::{eyre, Result, WrapErr};
-use paris::Logger;
 use serde::{Deserialize, Serialize};
 
+use std::path::Path;
 use std::time::Duration;
 
+use crate::events::{Event, EventSink};
 use crate::repositories::repository_contents::RepositoryContents;
 
 #[derive(Serialize, Deserialize)]
@@ -21,75 +22,78 @@ impl ::std::default::Default for HermioneConfig {
 }
 
 impl HermioneConfig {
-    pub fn load() -> Result<Self> {
-        let config: Self = confy::load("hermione")?;
+    /// Loads the config, which is the default when none was stored yet.
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let config_path = config_dir.join("hermione.toml");
+        if !config_path.is_file() {
+            return Ok(Self::default());
+        }
+        let config: Self = confy::load_path(config_path)?;
 
         Ok(config)
     }
 
-    pub fn store(self) -> Result<()> {
-        confy::store("hermione", self)?;
+    pub fn store(self, config_dir: &Path) -> Result<()> {
+        confy::store_path(config_dir.join("hermione.toml"), self)?;
 
         Ok(())
     }
 
-    pub fn available_repositories(&self) -> Result<Vec<RepositoryContents>> {
+    pub fn available_repositories(
+        &self,
+        events: &dyn EventSink,
+    ) -> Result<Vec<RepositoryContents>> {
         let client = reqwest::blocking::Client::builder()
             .timeout(Duration::from_secs(7))
             .build()?;
 
-        let available_repositories = self
-            .repository_urls
-            .iter()
-            .map(|repository_url| {
-                let mut logger = Logger::new();
-                logger.loading(format!("Fetching repository {}", repository_url));
-
-                let result = match client.get(repository_url).send() {
-                    Ok(response) => {
-                        if response.status().is_success() {
-                            match response.text() {
-                                Ok(text) => Ok(toml::from_str::<RepositoryContents>(&text)
-                                    .wrap_err_with(|| format!("Unable to deserialize TOML"))),
-                                Err(e) => {
-                                    Err(eyre!("Unable to decode response text to UTF-8: {}", e))
-                                }
-                            }
-                        } else {
-                            Err(eyre!(
-                                "HTTP request ({}) failed with status code {}",
-                                repository_url,
-                                response.status().as_str()
-                            ))
+        let available_repositories = self.repository_urls.iter().flat_map(|repository_url| {
+            events.emit(Event::info(format!(
+                "Fetching repository {}",
+                repository_url
+            )));
+
+            let result = match client.get(repository_url).send() {
+                Ok(response) => {
+                    if response.status().is_success() {
+                        match response.text() {
+                            Ok(text) => Ok(toml::from_str::<RepositoryContents>(&text)
+                                .wrap_err_with(|| "Unable to deserialize TOML".to_string())),
+                            Err(e) => Err(eyre!("Unable to decode response text to UTF-8: {}", e)),
                         }
+                    } else {
+                        Err(eyre!(
+                            "HTTP request ({}) failed with status code {}",
+                            repository_url,
+                            response.status().as_str()
+                        ))
                     }
-                    Err(err) => Err(eyre!(
-                        "Unable to fetch repository file from server: {}",
-                        err
-                    )),
-                };
-
-                if result.is_ok() {
-                    logger.success(format!("Fetched repository from {}", repository_url));
-                } else {
-                    logger.warn(format!(
-                        "Failed to fetch repository from {}",
-                        repository_url
-                    ));
-                };
-
-                result
-            })
-            .filter(|res| res.is_ok())
-            .map(|ok_res| ok_res.unwrap());
-
-        let mut logger = Logger::new();
-
-        logger.info("Finished repository fetch attempt.");
+                }
+                Err(err) => Err(eyre!(
+                    "Unable to fetch repository file from server: {}",
+                    err
+                )),
+            };
+
+            if result.is_ok() {
+                events.emit(Event::success(format!(
+                    "Fetched repository from {}",
+                    repository_url
+                )));
+            } else {
+                events.emit(Event::warning(format!(
+                    "Failed to fetch repository from {}",
+                    repository_url
+                )));
+            };
+
+            result
+        });
+
+        events.emit(Event::info("Finished repository fetch attempt."));
 
         let repos = available_repositories
-            .filter(|res| res.is_ok())
-            .map(|ok_res| ok_res.unwrap())
+            .flatten()
             .collect::<Vec<RepositoryContents>>();
 
         Ok(repos)
@@ -0,0 +1,72 @@
+use color_eyre::eyre::{eyre, Report, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// What to do when the output `o` of a file mapping already exists on the system.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// Move the existing file into the Hermione backup area and restore it on removal.
+    Backup,
+    /// Leave the existing file alone and do not install the mapping.
+    Skip,
+    /// Refuse to install the package.
+    #[default]
+    Fail,
+}
+
+impl ConflictStrategy {
+    /// Names accepted on the command line and in `hermione.yml`.
+    pub fn variants() -> &'static [&'static str] {
+        &["backup", "skip", "fail"]
+    }
+}
+
+impl FromStr for ConflictStrategy {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "backup" => Ok(ConflictStrategy::Backup),
+            "skip" => Ok(ConflictStrategy::Skip),
+            "fail" => Ok(ConflictStrategy::Fail),
+            other => Err(eyre!(
+                "Unknown conflict strategy '{}', expected one of: {}",
+                other,
+                Self::variants().join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ConflictStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ConflictStrategy::Backup => "backup",
+            ConflictStrategy::Skip => "skip",
+            ConflictStrategy::Fail => "fail",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_through_display() {
+        for name in ConflictStrategy::variants() {
+            let strategy: ConflictStrategy = name.parse().expect("Unable to parse strategy");
+            assert_eq!(*name, strategy.to_string());
+        }
+    }
+
+    #[test]
+    fn test_unknown_strategy_fails() {
+        assert!("overwrite".parse::<ConflictStrategy>().is_err());
+    }
+}
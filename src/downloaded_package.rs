@@ -1,15 +1,18 @@
 use color_eyre::eyre::{eyre, Result, WrapErr};
 use fs_extra::dir;
-use paris::Logger;
 
 use std::fs;
 use std::path::PathBuf;
 
-use crate::downloader::Downloader;
+use crate::error::HermioneError;
+use crate::events::Event;
 use crate::file_mapping::FileMapping;
 use crate::installed_package::InstalledPackage;
 use crate::manifest::Manifest;
 use crate::package_service::PackageService;
+use crate::plan::PlannedOperation;
+use crate::receipt::Receipt;
+use crate::versions::Versions;
 
 /// Downloaded state of a package.
 /// This means that a package is downloaded in cache
@@ -20,39 +23,59 @@ pub struct DownloadedPackage {
 }
 
 impl DownloadedPackage {
-    /// Installs the downloaded package.
+    /// Installs the downloaded package into `<install dir>/<id>/<version>`, makes it the
+    /// current version and removes old versions beyond `PackageService::keep_versions`.
+    /// A package whose local path already is its version directory is activated in place,
+    /// which is how kept versions are rolled back to.
     ///
     /// Returns InstalledPackage Result.
     pub fn install(self) -> Result<InstalledPackage> {
-        let mut logger = Logger::new();
-        let manifest_path = self.local_path.join(Manifest::manifest_file_name());
-        let manifest = Manifest::new_from_path(manifest_path)?;
+        let events = self.package_service.events.clone();
+        let manifest = Manifest::new_from_dir(&self.local_path)?;
         let package_id = manifest.id.clone();
+        let versions = Versions::new(&self.package_service, &package_id);
+        let install_path = versions.path(&manifest.version);
+        for path in manifest.files.keys() {
+            let file_path = self.local_path.join(path);
+            let valid = match fs::read(&file_path) {
+                Ok(contents) => manifest.verify_file_contents(path, &contents)?,
+                Err(_) => false,
+            };
+            events.emit(Event::IntegrityChecked {
+                path: file_path,
+                valid,
+            });
+            if !valid {
+                return Err(HermioneError::Integrity(format!(
+                    "Integrity Check Failed for {}!",
+                    path
+                ))
+                .into());
+            }
+        }
         let mapping_render_results = manifest
             .mappings
             .clone()
             .into_iter()
             .filter(|mapping_definition| mapping_definition.valid_platform_family())
             .map(|mapping_definition| {
-                let location = self
-                    .package_service
-                    .download_dir()
-                    .join(&package_id.as_str());
-                logger.info("Integrity Check").indent(1).log(format!(
-                    "Input file: {}",
-                    &location.join(&mapping_definition.i).display()
-                ));
+                let location = self.local_path.clone();
+                let input_path = location.join(&mapping_definition.i);
                 match mapping_definition.verify_integrity(location) {
                     Ok(valid) => {
+                        events.emit(Event::IntegrityChecked {
+                            path: input_path,
+                            valid,
+                        });
                         if valid {
-                            mapping_definition.render_file_mapping(
-                                &self.package_service,
-                                self.package_service
-                                    .install_dir()
-                                    .join(&package_id.as_str()),
-                            )
+                            mapping_definition
+                                .render_file_mapping(&self.package_service, install_path.clone())
                         } else {
-                            Err(eyre!("Integrity Check Failed!"))
+                            Err(HermioneError::Integrity(format!(
+                                "Integrity Check Failed for {}!",
+                                &mapping_definition.i
+                            ))
+                            .into())
                         }
                     }
                     Err(e) => Err(eyre!(
@@ -64,17 +87,17 @@ impl DownloadedPackage {
             })
             .collect::<Vec<_>>();
 
-        let mapping_render_errors = mapping_render_results
-            .iter()
-            .filter(|result| result.is_err())
-            .collect::<Vec<_>>();
-        if !mapping_render_errors.is_empty() {
-            mapping_render_errors
+        let (mapping_render_results, mapping_render_errors): (Vec<_>, Vec<_>) =
+            mapping_render_results
                 .into_iter()
-                .for_each(|error| eprintln!("{:?}", error));
-            Err(eyre!("Unable to install package"))
+                .partition(|result| result.is_ok());
+        let mut mapping_render_errors = mapping_render_errors.into_iter().filter_map(Result::err);
+        if let Some(first_error) = mapping_render_errors.next() {
+            mapping_render_errors
+                .for_each(|error| events.emit(Event::error(format!("{:#}", error))));
+            Err(first_error.wrap_err("Unable to install package"))
         } else {
-            logger.info("Running preflight check");
+            events.emit(Event::info("Running preflight check"));
 
             let validated_mappings = self
                 .validate_mappings(mapping_render_results)
@@ -82,38 +105,100 @@ impl DownloadedPackage {
                     "Bailing on Install! Not all file mappings are valid.".to_string()
                 })?;
 
-            let mut copy_options = dir::CopyOptions::new();
-            copy_options.copy_inside = true;
-            copy_options.overwrite = true;
-            let dest_path = self.package_service.install_dir();
-            if !dest_path.exists() {
-                logger.loading(format!(
-                    "Creating install directory: {}",
-                    &dest_path.display()
-                ));
-                dir::create_all(&dest_path, false)?;
-                logger.info("Successfully created install directory");
+            if self.local_path != install_path {
+                let package_root = versions.root().to_path_buf();
+                if !package_root.exists() {
+                    events.emit(Event::info(format!(
+                        "Creating install directory: {}",
+                        &package_root.display()
+                    )));
+                    self.package_service.perform(
+                        PlannedOperation::CreateDir {
+                            path: package_root.clone(),
+                        },
+                        || Ok(dir::create_all(&package_root, false)?),
+                    )?;
+                    events.emit(Event::info("Successfully created install directory"));
+                }
+                if versions.is_legacy() {
+                    self.package_service.perform(
+                        PlannedOperation::Remove {
+                            path: package_root.clone(),
+                        },
+                        || {
+                            fs::remove_dir_all(&package_root)?;
+                            Ok(fs::create_dir_all(&package_root)?)
+                        },
+                    )?;
+                }
+                events.emit(Event::info("Installing"));
+                if install_path.exists() {
+                    self.package_service.perform(
+                        PlannedOperation::Remove {
+                            path: install_path.clone(),
+                        },
+                        || Ok(fs::remove_dir_all(&install_path)?),
+                    )?;
+                }
+                let mut copy_options = dir::CopyOptions::new();
+                copy_options.copy_inside = true;
+                copy_options.overwrite = true;
+                self.package_service.perform(
+                    PlannedOperation::Copy {
+                        from: self.local_path.clone(),
+                        to: install_path.clone(),
+                    },
+                    || {
+                        dir::copy(&self.local_path, &install_path, &copy_options)?;
+                        Ok(())
+                    },
+                )?;
+                if let Ok(receipt) = Receipt::load(&self.local_path) {
+                    self.package_service.perform(
+                        PlannedOperation::Write {
+                            path: Receipt::receipt_path(&install_path),
+                        },
+                        || receipt.installed_now().store(&install_path),
+                    )?;
+                }
             }
-            logger.info("Installing");
-            dir::copy(&self.local_path, &dest_path, &copy_options)?;
-            let install_path = dest_path.join(&manifest.id);
 
             match &manifest.hooks {
-                Some(hooks) => hooks.execute_pre_install()?,
+                Some(hooks) => hooks.execute_pre_install(&self.package_service)?,
                 None => {
-                    logger.log("No pre_install hook");
+                    events.emit(Event::detail("No pre_install hook"));
                 }
             };
-            logger.info("Linking files");
+            events.emit(Event::info("Linking files"));
+            let mut skipped = Vec::new();
             for valid_mapping in validated_mappings {
-                logger.indent(1).log(valid_mapping.install()?);
+                if valid_mapping.is_skipped() {
+                    skipped.push(valid_mapping.output().to_path_buf());
+                }
+                valid_mapping.install(&self.package_service, &package_id)?;
+            }
+            // Skipped outputs belong to the user, verify and repair leave them alone
+            if let Ok(receipt) = Receipt::load(&install_path) {
+                if receipt.skipped != skipped {
+                    self.package_service.perform(
+                        PlannedOperation::Write {
+                            path: Receipt::receipt_path(&install_path),
+                        },
+                        || receipt.with_skipped(skipped).store(&install_path),
+                    )?;
+                }
             }
-            logger.success(format!("Successfully installed {}", &manifest.name));
+            versions.set_current(&manifest.version)?;
+            versions.prune(self.package_service.keep_versions)?;
+            events.emit(Event::success(format!(
+                "Successfully installed {}",
+                &manifest.name
+            )));
 
             match &manifest.hooks {
-                Some(hooks) => hooks.execute_post_install()?,
+                Some(hooks) => hooks.execute_post_install(&self.package_service)?,
                 None => {
-                    logger.log("No post_install hook");
+                    events.emit(Event::detail("No post_install hook"));
                 }
             };
 
@@ -127,34 +212,12 @@ impl DownloadedPackage {
 
     /// Remove the downloaded directory for the specified package.
     pub fn remove(&self) -> Result<()> {
-        fs::remove_dir_all(&self.local_path)?;
-        Ok(())
-    }
-
-    /// Upgrade the Downloaded package to the latest from the remote repo
-    ///
-    /// Returns a Result of InstalledPackage
-    pub fn upgrade(self) -> Result<InstalledPackage> {
-        let mut logger = Logger::new();
-        let manifest_path = self.local_path.join(Manifest::manifest_file_name());
-        let manifest = Manifest::new_from_path(manifest_path)?;
-        logger.loading(format!("Started upgrading {}", &manifest.name));
-
-        let downloader =
-            Downloader::new(String::from("TODO Implement"), self.package_service.clone());
-        match downloader.download() {
-            Ok(_) => {
-                logger.info("Finished fetching latest.");
-                self.install()
-            }
-            Err(e) => {
-                logger
-                    .error("Could not upgrade package, reverting back")
-                    .indent(1)
-                    .log(format!("<red>{}</>", e.to_string()));
-                self.install()
-            }
-        }
+        self.package_service.perform(
+            PlannedOperation::Remove {
+                path: self.local_path.clone(),
+            },
+            || Ok(fs::remove_dir_all(&self.local_path)?),
+        )
     }
 
     /// Checks that for a given vector of FileMapping results they all pass `pre_install_check()`
@@ -166,15 +229,16 @@ impl DownloadedPackage {
     ///
     /// Returns a Vector of FileMapping as a Result.
     fn validate_mappings(&self, mappings: Vec<Result<FileMapping>>) -> Result<Vec<FileMapping>> {
-        let mut logger = Logger::new();
-        logger.info("Validating mappings");
+        let events = &self.package_service.events;
+        events.emit(Event::info("Validating mappings"));
         mappings
             .into_iter()
             .map(|mapping_result| {
                 let mapping = mapping_result?;
-                logger
-                    .indent(1)
-                    .log(format!("OK: {}", mapping.pre_install_check()?));
+                events.emit(Event::detail(format!(
+                    "OK: {}",
+                    mapping.pre_install_check()?
+                )));
                 Ok(mapping)
             })
             .collect::<Result<Vec<_>>>()
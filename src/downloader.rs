@@ -1,11 +1,13 @@
 use color_eyre::eyre::Result;
-use paris::Logger;
+use ssri::{Algorithm, IntegrityOpts};
 use tempfile::Builder;
 
 use std::fs;
-use std::io::copy;
+use std::io::{Read, Write};
 
 use crate::downloaded_package::DownloadedPackage;
+use crate::error::HermioneError;
+use crate::events::Event;
 use crate::package_service::PackageService;
 use crate::packer::Packer;
 
@@ -23,42 +25,67 @@ impl Downloader {
         }
     }
 
-    pub fn download(self) -> Result<DownloadedPackage> {
-        let mut logger = Logger::new();
-
-        logger.loading(format!(
-            "Downloading hermione package from {}",
-            &self.remote_package_path
-        ));
+    /// Downloads and unpacks the remote package.
+    ///
+    /// Returns the DownloadedPackage along with the integrity of the downloaded archive as a Result.
+    pub fn download(self) -> Result<(DownloadedPackage, String)> {
+        let events = self.package_service.events.clone();
+        events.emit(Event::DownloadStarted {
+            source: self.remote_package_path.clone(),
+        });
 
         let tmp_dir = Builder::new().prefix("hermione_pkg_").tempdir()?;
-        let mut response = reqwest::blocking::get(&self.remote_package_path)?;
+        let mut response = reqwest::blocking::get(&self.remote_package_path)
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| {
+                HermioneError::Network(format!(
+                    "Unable to download {}: {}",
+                    &self.remote_package_path, e
+                ))
+            })?;
         let fname = response
             .url()
             .path_segments()
-            .and_then(|segments| segments.last())
+            .and_then(|mut segments| segments.next_back())
             .and_then(|name| if name.is_empty() { None } else { Some(name) })
             .unwrap_or("tmp.bin");
 
-        logger.success("Finished downloading file");
-        logger.info(format!("File to save: {}", &fname));
-
         let file_path_buf = tmp_dir.path().join(fname);
 
+        let total = response.content_length();
         let mut dest = fs::File::create(&file_path_buf)?;
-        copy(&mut response, &mut dest)?;
+        let mut buffer = [0; 64 * 1024];
+        let mut bytes = 0;
+        let mut integrity = IntegrityOpts::new().algorithm(Algorithm::Sha256);
+        loop {
+            let read = response.read(&mut buffer).map_err(|e| {
+                HermioneError::Network(format!(
+                    "Unable to download {}: {}",
+                    &self.remote_package_path, e
+                ))
+            })?;
+            if read == 0 {
+                break;
+            }
+            dest.write_all(&buffer[..read])?;
+            integrity.input(&buffer[..read]);
+            bytes += read as u64;
+            events.emit(Event::DownloadProgress { bytes, total });
+        }
 
-        logger.indent(1).log(format!(
-            "File Temporarly saved to: {}",
-            file_path_buf.display()
-        ));
+        events.emit(Event::DownloadFinished {
+            path: file_path_buf.clone(),
+            bytes,
+        });
 
-        let unpacked_archive_path =
-            Packer::new(file_path_buf).unpack(self.package_service.download_dir())?;
+        let unpacked_archive_path = Packer::new(file_path_buf)
+            .with_events(events)
+            .unpack(self.package_service.download_target_dir())?;
 
-        Ok(DownloadedPackage {
+        let downloaded_package = DownloadedPackage {
             local_path: unpacked_archive_path,
             package_service: self.package_service,
-        })
+        };
+        Ok((downloaded_package, integrity.result().to_string()))
     }
 }
@@ -0,0 +1,127 @@
+use color_eyre::eyre::Report;
+
+use std::error::Error;
+use std::fmt;
+
+/// Exit code for failures which are not a `HermioneError`.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Failures scripts may want to react to, each mapped to a stable process exit code.
+///
+/// These travel inside `eyre` reports like any other error, so context added with
+/// `wrap_err` does not hide them. Exit codes are part of the public interface,
+/// so they must never be renumbered.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HermioneError {
+    /// A manifest is missing or can not be parsed. Exits with 10.
+    Manifest(String),
+    /// A file does not match its recorded integrity. Exits with 11.
+    Integrity(String),
+    /// A file Hermione would write already exists. Exits with 12.
+    Conflict(String),
+    /// Another Hermione process holds the lockfile. Exits with 13.
+    Locked(String),
+    /// A remote package could not be fetched. Exits with 14.
+    Network(String),
+    /// A Duckscript hook failed. Exits with 15.
+    Hook(String),
+    /// The request was refused, e.g. a missing confirmation or an unsupported flag. Exits with 16.
+    Policy(String),
+    /// The named package is not installed. Exits with 17.
+    NotInstalled(String),
+    /// Installed files no longer match their package. Exits with 18.
+    Drift(String),
+    /// Newer versions of installed packages are available (`herm outdated`). Exits with 19.
+    Outdated(String),
+}
+
+impl HermioneError {
+    /// Returns the process exit code for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            HermioneError::Manifest(_) => 10,
+            HermioneError::Integrity(_) => 11,
+            HermioneError::Conflict(_) => 12,
+            HermioneError::Locked(_) => 13,
+            HermioneError::Network(_) => 14,
+            HermioneError::Hook(_) => 15,
+            HermioneError::Policy(_) => 16,
+            HermioneError::NotInstalled(_) => 17,
+            HermioneError::Drift(_) => 18,
+            HermioneError::Outdated(_) => 19,
+        }
+    }
+
+    /// Returns a short stable name for this kind of error.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            HermioneError::Manifest(_) => "manifest",
+            HermioneError::Integrity(_) => "integrity",
+            HermioneError::Conflict(_) => "conflict",
+            HermioneError::Locked(_) => "locked",
+            HermioneError::Network(_) => "network",
+            HermioneError::Hook(_) => "hook",
+            HermioneError::Policy(_) => "policy",
+            HermioneError::NotInstalled(_) => "not_installed",
+            HermioneError::Drift(_) => "drift",
+            HermioneError::Outdated(_) => "outdated",
+        }
+    }
+
+    /// Finds the outermost `HermioneError` in the chain of a report.
+    pub fn find(report: &Report) -> Option<&HermioneError> {
+        report
+            .chain()
+            .find_map(|error| error.downcast_ref::<HermioneError>())
+    }
+}
+
+impl fmt::Display for HermioneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HermioneError::Manifest(message)
+            | HermioneError::Integrity(message)
+            | HermioneError::Conflict(message)
+            | HermioneError::Locked(message)
+            | HermioneError::Network(message)
+            | HermioneError::Hook(message)
+            | HermioneError::Policy(message)
+            | HermioneError::NotInstalled(message)
+            | HermioneError::Drift(message)
+            | HermioneError::Outdated(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for HermioneError {}
+
+/// Returns the process exit code for a report.
+pub fn exit_code(report: &Report) -> i32 {
+    HermioneError::find(report)
+        .map(HermioneError::exit_code)
+        .unwrap_or(EXIT_FAILURE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use color_eyre::eyre::{eyre, WrapErr};
+
+    #[test]
+    fn test_exit_code_survives_context() {
+        let report = Err::<(), _>(HermioneError::Locked(String::from("busy")))
+            .wrap_err("Unable to start")
+            .unwrap_err();
+        assert_eq!(13, exit_code(&report));
+        assert_eq!(
+            Some("locked"),
+            HermioneError::find(&report).map(HermioneError::kind)
+        );
+    }
+
+    #[test]
+    fn test_untyped_error_exits_with_failure() {
+        assert_eq!(EXIT_FAILURE, exit_code(&eyre!("something else")));
+    }
+}
@@ -0,0 +1,257 @@
+use serde::Serialize;
+
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Something worth reporting while Hermione works.
+///
+/// Messages may contain [paris](https://crates.io/crates/paris) style tags such as `<green>`,
+/// which sinks that do not render colors strip.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// Progress worth mentioning, e.g. which step is running.
+    Info { message: String },
+    /// Detail belonging to the preceding step.
+    Detail { message: String },
+    /// A step finished successfully.
+    Success { message: String },
+    /// Something went wrong but Hermione carries on.
+    Warning { message: String },
+    /// Something went wrong and Hermione gives up.
+    Error { message: String },
+    /// A remote package started downloading.
+    DownloadStarted { source: String },
+    /// More of a remote package has been downloaded.
+    DownloadProgress { bytes: u64, total: Option<u64> },
+    /// A remote package has been downloaded to the given path.
+    DownloadFinished { path: PathBuf, bytes: u64 },
+    /// A package file was checked against its recorded integrity.
+    IntegrityChecked { path: PathBuf, valid: bool },
+    /// A mapping output now links to its package file.
+    MappingLinked { input: PathBuf, output: PathBuf },
+    /// An existing mapping output was moved aside before linking.
+    MappingBackedUp { output: PathBuf, backup_id: String },
+    /// An existing mapping output was left alone.
+    MappingSkipped { output: PathBuf },
+    /// A mapping output link was removed.
+    MappingUnlinked { output: PathBuf },
+    /// A mapping output was left in place because Hermione does not manage it.
+    MappingNotManaged { output: PathBuf },
+    /// A Duckscript hook started running.
+    HookStarted { hook: String },
+    /// A Duckscript hook echoed a line.
+    HookOutput { hook: String, line: String },
+    /// A Duckscript hook ran successfully.
+    HookFinished { hook: String },
+}
+
+impl Event {
+    pub fn info<T: Into<String>>(message: T) -> Self {
+        Event::Info {
+            message: message.into(),
+        }
+    }
+
+    pub fn detail<T: Into<String>>(message: T) -> Self {
+        Event::Detail {
+            message: message.into(),
+        }
+    }
+
+    pub fn success<T: Into<String>>(message: T) -> Self {
+        Event::Success {
+            message: message.into(),
+        }
+    }
+
+    pub fn warning<T: Into<String>>(message: T) -> Self {
+        Event::Warning {
+            message: message.into(),
+        }
+    }
+
+    pub fn error<T: Into<String>>(message: T) -> Self {
+        Event::Error {
+            message: message.into(),
+        }
+    }
+
+    /// Consumes the event and returns it with color tags removed from its message.
+    pub fn without_markup(self) -> Self {
+        match self {
+            Event::Info { message } => Event::info(strip_markup(&message)),
+            Event::Detail { message } => Event::detail(strip_markup(&message)),
+            Event::Success { message } => Event::success(strip_markup(&message)),
+            Event::Warning { message } => Event::warning(strip_markup(&message)),
+            Event::Error { message } => Event::error(strip_markup(&message)),
+            event => event,
+        }
+    }
+}
+
+/// Receives the events of Hermione operations.
+pub trait EventSink: fmt::Debug + Send + Sync {
+    fn emit(&self, event: Event);
+}
+
+/// Returns a sink which drops every event, the default for library users.
+pub fn silent() -> Arc<dyn EventSink> {
+    Arc::new(SilentSink {})
+}
+
+/// Drops every event.
+#[derive(Debug)]
+pub struct SilentSink {}
+
+impl EventSink for SilentSink {
+    fn emit(&self, _event: Event) {}
+}
+
+/// Renders events as colored log lines on stdout, the way `herm` always has.
+pub struct PrettySink {
+    logger: Mutex<paris::Logger>,
+}
+
+impl PrettySink {
+    pub fn new() -> Self {
+        Self {
+            logger: Mutex::new(paris::Logger::new()),
+        }
+    }
+}
+
+impl Default for PrettySink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for PrettySink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrettySink").finish()
+    }
+}
+
+impl EventSink for PrettySink {
+    fn emit(&self, event: Event) {
+        let mut logger = self.logger.lock().expect("Unable to lock logger");
+        match event {
+            Event::Info { message } => logger.info(message),
+            Event::Detail { message } => logger.indent(1).log(message),
+            Event::Success { message } => logger.success(message),
+            Event::Warning { message } => logger.warn(message),
+            Event::Error { message } => logger.error(message),
+            Event::DownloadStarted { source } => {
+                logger.loading(format!("Downloading hermione package from {}", source))
+            }
+            Event::DownloadProgress { .. } => &mut *logger,
+            Event::DownloadFinished { path, bytes } => logger.success(format!(
+                "Finished downloading {} bytes to {}",
+                bytes,
+                path.display()
+            )),
+            Event::IntegrityChecked { path, valid } => {
+                let state = if valid {
+                    "<green>Integrity OK</>"
+                } else {
+                    "<red>Integrity mismatch</>"
+                };
+                logger
+                    .indent(1)
+                    .log(format!("{} {}", state, path.display()))
+            }
+            Event::MappingLinked { input, output } => logger.indent(1).log(format!(
+                "<blue>Linking</> {} <blue>-></> {}",
+                input.display(),
+                output.display()
+            )),
+            Event::MappingBackedUp { output, backup_id } => logger.indent(1).log(format!(
+                "<yellow>Backed up</> {} as {}",
+                output.display(),
+                backup_id
+            )),
+            Event::MappingSkipped { output } => logger.indent(1).log(format!(
+                "<yellow>Skipped</> {} because it already exists",
+                output.display()
+            )),
+            Event::MappingUnlinked { output } => logger
+                .indent(1)
+                .log(format!("<yellow>Unlinked</> {}", output.display())),
+            Event::MappingNotManaged { output } => logger.indent(1).log(format!(
+                "Not removing {} because it is not managed by Hermione",
+                output.display()
+            )),
+            Event::HookStarted { hook } => logger.info(format!("Initiated {} hook", hook)),
+            Event::HookOutput { line, .. } => logger.log(line),
+            Event::HookFinished { hook } => {
+                logger.success(format!("Finished running {} hook", hook))
+            }
+        };
+    }
+}
+
+/// Writes every event as a line of JSON to stderr, leaving stdout to the command's result.
+#[derive(Debug, Default)]
+pub struct JsonSink {}
+
+impl EventSink for JsonSink {
+    fn emit(&self, event: Event) {
+        if let Ok(line) = serde_json::to_string(&event.without_markup()) {
+            let _ = writeln!(std::io::stderr(), "{}", line);
+        }
+    }
+}
+
+/// Removes paris style tags like `<green>` and `</>` from a message.
+fn strip_markup(message: &str) -> String {
+    let mut stripped = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(start) = rest.find('<') {
+        stripped.push_str(&rest[..start]);
+        let tag = &rest[start..];
+        match tag.find('>') {
+            Some(end)
+                if tag[1..end]
+                    .chars()
+                    .all(|c| c.is_ascii_alphabetic() || c == '_' || c == '/') =>
+            {
+                rest = &tag[end + 1..];
+            }
+            _ => {
+                stripped.push('<');
+                rest = &tag[1..];
+            }
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_markup() {
+        assert_eq!(
+            "Linking a -> b",
+            strip_markup("<blue>Linking</> a <blue>-></> b")
+        );
+        assert_eq!("a < b", strip_markup("a < b"));
+    }
+
+    #[test]
+    fn test_json_event_shape() {
+        let event = Event::MappingLinked {
+            input: PathBuf::from("a"),
+            output: PathBuf::from("b"),
+        };
+        assert_eq!(
+            "{\"event\":\"mapping_linked\",\"input\":\"a\",\"output\":\"b\"}",
+            serde_json::to_string(&event).expect("Unable to serialize event in test")
+        );
+    }
+}
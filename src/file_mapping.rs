@@ -4,7 +4,14 @@ use color_eyre::eyre::{eyre, Result, WrapErr};
 use quickcheck_macros::quickcheck;
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::backup::Backup;
+use crate::conflict_strategy::ConflictStrategy;
+use crate::error::HermioneError;
+use crate::events::Event;
+use crate::package_service::PackageService;
+use crate::plan::PlannedOperation;
 
 #[cfg(target_family = "unix")]
 use std::os::unix::fs::symlink;
@@ -20,6 +27,8 @@ pub struct FileMapping {
     i: PathBuf,
     /// output PathBuf - Where you would like it to go on the system.
     o: PathBuf,
+    /// What to do when the output file already exists.
+    on_conflict: ConflictStrategy,
 }
 
 impl FileMapping {
@@ -31,7 +40,19 @@ impl FileMapping {
     /// * o - `PathBuf` of the output file path.
     ///
     pub fn new(i: PathBuf, o: PathBuf) -> Self {
-        Self { i, o }
+        Self {
+            i,
+            o,
+            on_conflict: ConflictStrategy::default(),
+        }
+    }
+
+    /// Consumes the FileMapping and sets the conflict strategy.
+    pub fn with_on_conflict(self, on_conflict: ConflictStrategy) -> Self {
+        Self {
+            on_conflict,
+            ..self
+        }
     }
 
     /// Returns String print out of File Mapping.
@@ -43,85 +64,185 @@ impl FileMapping {
         )
     }
 
-    /// Returns an error if the output file already exists.
+    /// Returns an error if the output file already exists and the conflict strategy is `fail`.
     pub fn pre_install_check(&self) -> Result<String> {
-        if !self.o.exists() {
+        if self.is_linked() {
+            Ok(format!("{} is <green>already linked</>", self.o.display()))
+        } else if !self.o.exists() {
             Ok(format!("{} is <green>valid</>", self.o.display()))
         } else {
-            Err(eyre!(
-                "Install to ({}) is NOT valid! File already exists, Hermione will not overwrite.",
-                self.o.display()
-            ))
+            match self.on_conflict {
+                ConflictStrategy::Backup => Ok(format!(
+                    "{} exists and will be <yellow>backed up</>",
+                    self.o.display()
+                )),
+                ConflictStrategy::Skip => Ok(format!(
+                    "{} exists and will be <yellow>skipped</>",
+                    self.o.display()
+                )),
+                ConflictStrategy::Fail => Err(HermioneError::Conflict(format!(
+                    "Install to ({}) is NOT valid! File already exists, Hermione will not overwrite.",
+                    self.o.display()
+                ))
+                .into()),
+            }
         }
     }
 
-    /// Installs the input file to the output path.
+    /// Returns true when installing leaves an existing output alone because the conflict strategy is `skip`.
+    pub fn is_skipped(&self) -> bool {
+        self.o.exists() && !self.is_linked() && self.on_conflict == ConflictStrategy::Skip
+    }
+
+    /// Installs the input file to the output path, resolving any
+    /// pre-existing output file according to the conflict strategy.
     ///
-    /// Returns String as a Result.
-    pub fn install(&self) -> Result<String> {
-        let link_file = self.i.exists() && !self.o.exists();
-        if let Some(parent_path) = self.o.parent() {
-            if !parent_path.exists() {
-                fs::create_dir_all(parent_path)?;
+    /// ### Arguments
+    ///
+    /// * package_service - Borrowed reference to PackageService.
+    /// * package_id - Id of the package being installed.
+    ///
+    /// Returns an empty Result.
+    pub fn install(&self, package_service: &PackageService, package_id: &str) -> Result<()> {
+        if self.o.exists() && !self.is_linked() {
+            match self.on_conflict {
+                ConflictStrategy::Backup => {
+                    let mut backup_id = String::from("planned");
+                    package_service.perform(
+                        PlannedOperation::Backup {
+                            path: self.o.clone(),
+                        },
+                        || {
+                            let backup =
+                                Backup::create(&package_service.backup_dir(), package_id, &self.o)?;
+                            backup_id = backup.id;
+                            Ok(())
+                        },
+                    )?;
+                    package_service.events.emit(Event::MappingBackedUp {
+                        output: self.o.clone(),
+                        backup_id,
+                    });
+                    return self.link(package_service);
+                }
+                ConflictStrategy::Skip => {
+                    package_service.events.emit(Event::MappingSkipped {
+                        output: self.o.clone(),
+                    });
+                    return Ok(());
+                }
+                ConflictStrategy::Fail => {}
             }
         }
-        if link_file {
-            #[cfg(target_family = "windows")]
-            let link_result = symlink_file(&self.i, &self.o);
+        self.link(package_service)
+    }
 
-            #[cfg(target_family = "unix")]
-            let link_result = symlink(&self.i, &self.o);
+    /// Links the input file to the output path.
+    ///
+    /// Returns an empty Result.
+    fn link(&self, package_service: &PackageService) -> Result<()> {
+        package_service.perform(
+            PlannedOperation::Link {
+                path: self.o.clone(),
+                target: self.i.clone(),
+            },
+            || {
+                if self.is_linked() {
+                    return Ok(());
+                }
+                let link_file = self.i.exists() && !self.o.exists();
+                if let Some(parent_path) = self.o.parent() {
+                    if !parent_path.exists() {
+                        fs::create_dir_all(parent_path)?;
+                    }
+                }
+                if link_file {
+                    #[cfg(target_family = "windows")]
+                    let link_result = symlink_file(&self.i, &self.o);
+
+                    #[cfg(target_family = "unix")]
+                    let link_result = symlink(&self.i, &self.o);
+
+                    link_result.wrap_err_with(|| {
+                        format!(
+                            "Failed to link file {} -> {}",
+                            self.i.display(),
+                            self.o.display()
+                        )
+                    })
+                } else if self.o.exists() {
+                    Err(HermioneError::Conflict(format!(
+                        "{} exists and Hermione will not overwrite it.",
+                        self.o.display()
+                    ))
+                    .into())
+                } else {
+                    Err(eyre!(
+                        "Unable to install from {} -> {}",
+                        self.i.display(),
+                        self.o.display()
+                    ))
+                }
+            },
+        )?;
+
+        package_service.events.emit(Event::MappingLinked {
+            input: self.i.clone(),
+            output: self.o.clone(),
+        });
+        Ok(())
+    }
 
-            link_result.wrap_err_with(|| {
-                format!(
-                    "Failed to link file {} -> {}",
-                    self.i.display(),
-                    self.o.display()
-                )
-            })?;
-
-            Ok(self.display_line())
-        } else if self.o.exists() {
-            Err(eyre!(
-                "{} exists and Hermione will not overwrite it.",
-                self.o.display()
-            ))
-        } else {
-            Err(eyre!(
-                "Unable to install from {} -> {}",
-                self.i.display(),
-                self.o.display()
-            ))
+    /// Returns the input path inside the package.
+    pub fn input(&self) -> &Path {
+        &self.i
+    }
+
+    /// Returns the output path on the system.
+    pub fn output(&self) -> &Path {
+        &self.o
+    }
+
+    /// Returns true if the output path is a link to the input file.
+    pub fn is_linked(&self) -> bool {
+        match fs::read_link(&self.o) {
+            Ok(target) => target == self.i,
+            Err(_) => false,
         }
     }
 
-    /// Uninstalls the output path defined in the manifest
+    /// Uninstalls the output path defined in the manifest.
+    /// Only links created by Hermione are removed.
     ///
-    /// Returns String Result
-    pub fn uninstall(self) -> Result<String> {
-        if self.o.is_file() {
-            fs::remove_file(&self.o)?;
-            Ok(format!("<yellow>Unlinked</> {}", self.o.display()))
+    /// Returns an empty Result.
+    pub fn uninstall(self, package_service: &PackageService) -> Result<()> {
+        if self.is_linked() {
+            package_service.perform(
+                PlannedOperation::Unlink {
+                    path: self.o.clone(),
+                },
+                || Ok(fs::remove_file(&self.o)?),
+            )?;
+            package_service
+                .events
+                .emit(Event::MappingUnlinked { output: self.o });
         } else {
-            Ok(format!(
-                "Not removing {} because it is not a file",
-                self.o.display()
-            ))
+            package_service
+                .events
+                .emit(Event::MappingNotManaged { output: self.o });
         }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::Path;
 
     #[quickcheck]
     fn test_display_line(a: String, b: String) -> bool {
-        let file_mapping = FileMapping {
-            i: Path::new(&a).to_path_buf(),
-            o: Path::new(&b).to_path_buf(),
-        };
+        let file_mapping =
+            FileMapping::new(Path::new(&a).to_path_buf(), Path::new(&b).to_path_buf());
         let display_line = file_mapping.display_line();
 
         display_line.contains(&a) && display_line.contains(&b) && display_line.contains("->")
@@ -1,4 +1,5 @@
 use color_eyre::eyre::{eyre, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use ssri::{Integrity, IntegrityChecker};
 use tera::{Context, Tera};
@@ -6,6 +7,7 @@ use tera::{Context, Tera};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::conflict_strategy::ConflictStrategy;
 use crate::file_mapping::FileMapping;
 use crate::package_service::PackageService;
 
@@ -16,7 +18,7 @@ const PLATFORM: &str = "unix";
 const PLATFORM: &str = "windows";
 
 /// Mapping Definitions are where you put the input `i` files and the output `o` location
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub struct FileMappingDefinition {
     /// Input file path - Where is the desired file in the package.
     pub i: String,
@@ -26,6 +28,8 @@ pub struct FileMappingDefinition {
     pub platform: Option<String>,
     /// Subresource Integrity (SRI) according to https://w3c.github.io/webappsec-subresource-integrity/
     pub integrity: Option<String>,
+    /// What to do when the output file already exists, unless overridden at install time
+    pub on_conflict: Option<ConflictStrategy>,
 }
 
 impl FileMappingDefinition {
@@ -35,12 +39,19 @@ impl FileMappingDefinition {
     ///
     /// * `i` - `String` input file path.
     /// * `o` - `String` output file path.
-    pub fn new(i: String, o: String, platform: Option<String>, integrity: Option<String>) -> Self {
+    pub fn new(
+        i: String,
+        o: String,
+        platform: Option<String>,
+        integrity: Option<String>,
+        on_conflict: Option<ConflictStrategy>,
+    ) -> Self {
         FileMappingDefinition {
             i,
             o,
             platform,
             integrity,
+            on_conflict,
         }
     }
 
@@ -56,14 +67,18 @@ impl FileMappingDefinition {
         package_path_buf: PathBuf,
     ) -> Result<FileMapping> {
         let mut context = Context::new();
-        let home_dir_path_buf = package_service.home_dir()?;
-        let home_dir = home_dir_path_buf.to_string_lossy();
-        context.insert("HOME", &home_dir);
+        for (name, path_buf) in package_service.facts()? {
+            context.insert(name, &path_buf.to_string_lossy());
+        }
         match Tera::one_off(&self.o, &context, false) {
             Ok(o) => {
                 let i_path = package_path_buf.join(&self.i);
                 let o_path = Path::new(&o).to_path_buf();
-                Ok(FileMapping::new(i_path, o_path))
+                let on_conflict = package_service
+                    .on_conflict
+                    .or(self.on_conflict)
+                    .unwrap_or_default();
+                Ok(FileMapping::new(i_path, o_path).with_on_conflict(on_conflict))
             }
             Err(e) => Err(eyre!(
                 "Unable to calculate file mapping {} because {}",
@@ -82,12 +97,24 @@ impl FileMappingDefinition {
     }
 
     pub fn verify_integrity(&self, directory_location: PathBuf) -> Result<bool> {
+        self.verify_integrity_of(&directory_location.join(&self.i))
+    }
+
+    /// Returns true if the contents of the given file match the integrity of this mapping.
+    pub fn verify_integrity_of(&self, file_path: &Path) -> Result<bool> {
+        match &self.integrity {
+            Some(_) => self.verify_integrity_of_contents(&fs::read(file_path)?),
+            None => Ok(false),
+        }
+    }
+
+    /// Returns true if the given contents match the integrity of this mapping.
+    pub fn verify_integrity_of_contents(&self, contents: &[u8]) -> Result<bool> {
         match &self.integrity {
             Some(checksum) => {
                 let parsed: Integrity = checksum.parse()?;
                 let mut checker = IntegrityChecker::new(parsed);
-                let file_contents = fs::read(directory_location.join(&self.i))?;
-                checker.input(&file_contents);
+                checker.input(contents);
 
                 Ok(checker.result().is_ok())
             }
@@ -0,0 +1,575 @@
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::downloaded_package::DownloadedPackage;
+use crate::error::HermioneError;
+use crate::history::{History, HistoryEntry, HistoryOperation};
+use crate::installed_package::InstalledPackage;
+use crate::manifest::Manifest;
+use crate::package_service::PackageService;
+use crate::pins::Pins;
+use crate::receipt::Receipt;
+
+/// A Hermfile lists the packages a machine should have installed, e.g.
+///
+/// ```yaml
+/// packages:
+///   - source: https://example.com/dotfiles.hpkg
+///     version: ">=1.2"
+///   - id: org.hermione.example-package
+/// ```
+///
+/// It may also be written as TOML with a `[[packages]]` table per entry.
+/// `herm export` writes one pinning every installed package to its exact version and archive.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hermfile {
+    #[serde(default)]
+    pub packages: Vec<HermfileEntry>,
+}
+
+/// A package wanted by a Hermfile.
+/// Unknown keys are rejected rather than ignored, so e.g. per package `features:` or
+/// `variables:`, which Hermfiles do not support, fail loudly instead of syncing without them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HermfileEntry {
+    /// Where to install the package from, in any form `herm install` accepts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Package id, enough on its own for packages which are already installed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Semver requirement the installed version has to meet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Subresource integrity the package archive downloaded from the source has to match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
+}
+
+/// A change `herm sync` makes to bring the installed packages in line with a Hermfile.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SyncStep {
+    Install {
+        id: String,
+        version: String,
+        source: String,
+        #[serde(skip)]
+        local_path: PathBuf,
+    },
+    Upgrade {
+        id: String,
+        from: String,
+        to: String,
+        source: String,
+        #[serde(skip)]
+        local_path: PathBuf,
+    },
+    /// Replaced by an older version because the Hermfile's requirement excludes the installed one.
+    Downgrade {
+        id: String,
+        from: String,
+        to: String,
+        source: String,
+        #[serde(skip)]
+        local_path: PathBuf,
+    },
+    Keep {
+        id: String,
+        version: String,
+    },
+    Remove {
+        id: String,
+        version: String,
+    },
+    /// Installed but not listed, left alone because pruning was not requested.
+    Unlisted {
+        id: String,
+        version: String,
+    },
+    /// Left at its installed version because it is pinned.
+    Held {
+        id: String,
+        version: String,
+        pin: String,
+    },
+}
+
+impl fmt::Display for SyncStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncStep::Install {
+                id,
+                version,
+                source,
+                ..
+            } => write!(f, "<green>install</> {} {} from {}", id, version, source),
+            SyncStep::Upgrade {
+                id,
+                from,
+                to,
+                source,
+                ..
+            } => write!(
+                f,
+                "<blue>upgrade</> {} {} -> {} from {}",
+                id, from, to, source
+            ),
+            SyncStep::Downgrade {
+                id,
+                from,
+                to,
+                source,
+                ..
+            } => write!(
+                f,
+                "<yellow>downgrade</> {} {} -> {} from {}",
+                id, from, to, source
+            ),
+            SyncStep::Keep { id, version } => write!(f, "keep {} {}", id, version),
+            SyncStep::Remove { id, version } => write!(f, "<red>remove</> {} {}", id, version),
+            SyncStep::Unlisted { id, version } => write!(
+                f,
+                "<yellow>unlisted</> {} {}, pass --prune to remove it",
+                id, version
+            ),
+            SyncStep::Held { id, version, pin } => write!(
+                f,
+                "<yellow>held</> {} at {}, pinned to {}",
+                id, version, pin
+            ),
+        }
+    }
+}
+
+impl SyncStep {
+    /// Returns true when the step leaves the package as it is.
+    pub fn is_noop(&self) -> bool {
+        matches!(
+            self,
+            SyncStep::Keep { .. } | SyncStep::Unlisted { .. } | SyncStep::Held { .. }
+        )
+    }
+
+    /// Carries out the step.
+    ///
+    /// Returns an empty Result.
+    pub fn apply(&self, package_service: &PackageService) -> Result<()> {
+        match self {
+            SyncStep::Install { id, local_path, .. } => {
+                let entry = HistoryEntry::new(HistoryOperation::Install, id);
+                History::new(package_service).track(entry, |entry| {
+                    let installed_package = DownloadedPackage {
+                        local_path: local_path.clone(),
+                        package_service: package_service.clone(),
+                    }
+                    .install()?;
+                    Ok(((), entry.after(&installed_package)))
+                })?;
+            }
+            SyncStep::Upgrade { id, local_path, .. }
+            | SyncStep::Downgrade { id, local_path, .. } => {
+                let current = package_service.clone().get_installed_package(id.clone())?;
+                let entry = HistoryEntry::new(HistoryOperation::Upgrade, id).before(&current);
+                History::new(package_service).track(entry, |entry| {
+                    let installed_package = current.replace_with(DownloadedPackage {
+                        local_path: local_path.clone(),
+                        package_service: package_service.clone(),
+                    })?;
+                    Ok(((), entry.after(&installed_package)))
+                })?;
+            }
+            SyncStep::Remove { id, .. } => {
+                package_service
+                    .clone()
+                    .get_installed_package(id.clone())?
+                    .remove()?;
+            }
+            SyncStep::Keep { .. } | SyncStep::Unlisted { .. } | SyncStep::Held { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+impl Hermfile {
+    /// Loads a Hermfile, which is read as TOML when it has a `.toml` extension and as YAML otherwise.
+    /// Files without an extension are tried as YAML first and then as TOML.
+    /// Relative `file://./…` and `file://../…` sources are resolved against the Hermfile's directory.
+    ///
+    /// Returns a Hermfile as a Result.
+    pub fn new_from_path(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            HermioneError::Manifest(format!("Unable to read Hermfile {}: {}", path.display(), e))
+        })?;
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        let parsed = match extension {
+            Some("toml") => Self::from_toml(&contents),
+            Some("yml") | Some("yaml") => Self::from_yaml(&contents),
+            _ => Self::from_yaml(&contents).or_else(|yaml_error| {
+                Self::from_toml(&contents).map_err(|toml_error| {
+                    HermioneError::Manifest(format!("{}, {}", yaml_error, toml_error)).into()
+                })
+            }),
+        };
+        let hermfile =
+            parsed.wrap_err_with(|| format!("Unable to parse Hermfile {}", path.display()))?;
+        hermfile.check()?;
+        let base_dir = match path.parent() {
+            Some(parent) if parent != Path::new("") => fs::canonicalize(parent)?,
+            _ => fs::canonicalize(".")?,
+        };
+        Ok(hermfile.with_sources_relative_to(&base_dir))
+    }
+
+    /// Consumes the Hermfile and makes relative `file://` sources absolute below the given directory,
+    /// so a checked in Hermfile works no matter where `herm sync` runs.
+    fn with_sources_relative_to(self, base_dir: &Path) -> Self {
+        let packages = self
+            .packages
+            .into_iter()
+            .map(|entry| {
+                let source = entry
+                    .source
+                    .map(|source| match source.strip_prefix("file://") {
+                        Some(relative)
+                            if relative.starts_with("./") || relative.starts_with("../") =>
+                        {
+                            let path = base_dir.join(relative);
+                            let path = fs::canonicalize(&path).unwrap_or(path);
+                            format!("file://{}", path.display())
+                        }
+                        _ => source,
+                    });
+                HermfileEntry { source, ..entry }
+            })
+            .collect();
+        Self { packages }
+    }
+
+    /// Returns a Hermfile pinning every installed package to its exact version,
+    /// recorded source and archive integrity.
+    pub fn export(package_service: &PackageService) -> Result<Self> {
+        let packages = package_service
+            .list_installed_packages()?
+            .into_iter()
+            .map(|installed_package| {
+                let receipt = Receipt::load(&installed_package.local_path).ok();
+                HermfileEntry {
+                    source: receipt.as_ref().map(|receipt| receipt.source.clone()),
+                    id: Some(installed_package.manifest.id),
+                    version: Some(format!("={}", installed_package.manifest.version)),
+                    integrity: receipt.and_then(|receipt| receipt.integrity),
+                }
+            })
+            .collect();
+        Ok(Self { packages })
+    }
+
+    /// Returns the Hermfile as TOML when the path has a `.toml` extension and as YAML otherwise.
+    pub fn to_string_for_path(&self, path: &Path) -> Result<String> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Ok(toml::to_string(self)?),
+            _ => Ok(serde_yaml::to_string(self)?),
+        }
+    }
+
+    fn from_yaml(contents: &str) -> Result<Self> {
+        serde_yaml::from_str(contents)
+            .map_err(|e| HermioneError::Manifest(format!("Invalid YAML: {}", e)).into())
+    }
+
+    fn from_toml(contents: &str) -> Result<Self> {
+        toml::from_str(contents)
+            .map_err(|e| HermioneError::Manifest(format!("Invalid TOML: {}", e)).into())
+    }
+
+    /// Errors unless every entry names a source or an id and has a valid version requirement.
+    fn check(&self) -> Result<()> {
+        for (index, entry) in self.packages.iter().enumerate() {
+            if entry.source.is_none() && entry.id.is_none() {
+                return Err(HermioneError::Manifest(format!(
+                    "Hermfile entry {} needs a source or an id",
+                    index + 1
+                ))
+                .into());
+            }
+            entry.version_req()?;
+        }
+        Ok(())
+    }
+
+    /// Works out what has to change for the installed packages to match this Hermfile.
+    /// Listed sources are downloaded to learn their id and version.
+    ///
+    /// ### Arguments
+    ///
+    /// * package_service - Service the packages are installed with.
+    /// * prune - Whether installed packages missing from the Hermfile are removed.
+    ///
+    /// Pinned packages are held at their installed version when the Hermfile asks for a
+    /// version their pin does not allow, and are never pruned.
+    ///
+    /// Returns a vector of SyncStep as a Result.
+    pub fn plan(&self, package_service: &PackageService, prune: bool) -> Result<Vec<SyncStep>> {
+        let installed = package_service.list_installed_packages()?;
+        let pins = Pins::load(package_service)?;
+        let mut listed = HashSet::new();
+        let mut steps = Vec::new();
+
+        for entry in &self.packages {
+            let version_req = entry.version_req()?;
+            let current = match &entry.id {
+                Some(id) => installed
+                    .iter()
+                    .find(|installed_package| &installed_package.manifest.id == id),
+                None => None,
+            };
+            let wanted = match current {
+                Some(installed_package) => is_outdated(installed_package, &version_req)?,
+                None => true,
+            };
+
+            let step = if let (Some(installed_package), false) = (current, wanted) {
+                SyncStep::Keep {
+                    id: installed_package.manifest.id.clone(),
+                    version: installed_package.manifest.version.clone(),
+                }
+            } else {
+                let source = match (&entry.source, current) {
+                    (Some(source), _) => source.clone(),
+                    (None, Some(installed_package)) => {
+                        Receipt::load(&installed_package.local_path)?.source
+                    }
+                    (None, None) => {
+                        return Err(HermioneError::NotInstalled(format!(
+                            "{} is not installed and the Hermfile gives no source for it",
+                            entry.id.clone().unwrap_or_default()
+                        ))
+                        .into())
+                    }
+                };
+                let downloaded = package_service.clone().download(source.clone())?;
+                if let Some(integrity) = &entry.integrity {
+                    let downloaded_integrity = Receipt::load(&downloaded.local_path)?.integrity;
+                    if downloaded_integrity.as_ref() != Some(integrity) {
+                        return Err(HermioneError::Integrity(format!(
+                            "{} does not match the integrity {} in the Hermfile",
+                            source, integrity
+                        ))
+                        .into());
+                    }
+                }
+                let manifest = Manifest::new_from_dir(&downloaded.local_path)?;
+                if let Some(id) = &entry.id {
+                    if id != &manifest.id {
+                        return Err(eyre!(
+                            "Hermfile lists {} but {} provides {}",
+                            id,
+                            source,
+                            manifest.id
+                        ));
+                    }
+                }
+                if let Some(version_req) = &version_req {
+                    if !version_req.matches(&Version::parse(&manifest.version)?) {
+                        return Err(eyre!(
+                            "{} provides {} {} which does not meet the requirement {}",
+                            source,
+                            manifest.id,
+                            manifest.version,
+                            version_req
+                        ));
+                    }
+                }
+
+                match installed
+                    .iter()
+                    .find(|installed_package| installed_package.manifest.id == manifest.id)
+                {
+                    Some(installed_package) if !is_outdated(installed_package, &version_req)? => {
+                        SyncStep::Keep {
+                            id: manifest.id,
+                            version: installed_package.manifest.version.clone(),
+                        }
+                    }
+                    Some(installed_package) if !pins.allows(&manifest.id, &manifest.version)? => {
+                        SyncStep::Held {
+                            pin: pins.get(&manifest.id).cloned().unwrap_or_default(),
+                            id: manifest.id,
+                            version: installed_package.manifest.version.clone(),
+                        }
+                    }
+                    Some(installed_package)
+                        if Version::parse(&manifest.version)?
+                            < Version::parse(&installed_package.manifest.version)? =>
+                    {
+                        SyncStep::Downgrade {
+                            id: manifest.id,
+                            from: installed_package.manifest.version.clone(),
+                            to: manifest.version,
+                            source,
+                            local_path: downloaded.local_path,
+                        }
+                    }
+                    Some(installed_package) => SyncStep::Upgrade {
+                        id: manifest.id,
+                        from: installed_package.manifest.version.clone(),
+                        to: manifest.version,
+                        source,
+                        local_path: downloaded.local_path,
+                    },
+                    None if !pins.allows(&manifest.id, &manifest.version)? => {
+                        return Err(HermioneError::Policy(format!(
+                            "{} {} is not allowed by its pin {}",
+                            manifest.id,
+                            manifest.version,
+                            pins.get(&manifest.id).cloned().unwrap_or_default()
+                        ))
+                        .into())
+                    }
+                    None => SyncStep::Install {
+                        id: manifest.id,
+                        version: manifest.version,
+                        source,
+                        local_path: downloaded.local_path,
+                    },
+                }
+            };
+
+            let id = match &step {
+                SyncStep::Install { id, .. }
+                | SyncStep::Upgrade { id, .. }
+                | SyncStep::Downgrade { id, .. }
+                | SyncStep::Keep { id, .. }
+                | SyncStep::Remove { id, .. }
+                | SyncStep::Unlisted { id, .. }
+                | SyncStep::Held { id, .. } => id.clone(),
+            };
+            if !listed.insert(id.clone()) {
+                return Err(HermioneError::Manifest(format!(
+                    "Hermfile lists {} more than once",
+                    id
+                ))
+                .into());
+            }
+            steps.push(step);
+        }
+
+        for installed_package in installed {
+            if !listed.contains(&installed_package.manifest.id) {
+                let id = installed_package.manifest.id;
+                let version = installed_package.manifest.version;
+                steps.push(if let Some(pin) = pins.get(&id) {
+                    SyncStep::Held {
+                        pin: pin.clone(),
+                        id,
+                        version,
+                    }
+                } else if prune {
+                    SyncStep::Remove { id, version }
+                } else {
+                    SyncStep::Unlisted { id, version }
+                });
+            }
+        }
+
+        Ok(steps)
+    }
+}
+
+impl HermfileEntry {
+    /// Returns the parsed version requirement, if any.
+    pub fn version_req(&self) -> Result<Option<VersionReq>> {
+        match &self.version {
+            Some(version) => VersionReq::parse(version).map(Some).map_err(|e| {
+                HermioneError::Manifest(format!(
+                    "Invalid version requirement {} in Hermfile: {}",
+                    version, e
+                ))
+                .into()
+            }),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Returns true when an installed package is below the version requirement.
+fn is_outdated(
+    installed_package: &InstalledPackage,
+    version_req: &Option<VersionReq>,
+) -> Result<bool> {
+    match version_req {
+        Some(version_req) => {
+            Ok(!version_req.matches(&Version::parse(&installed_package.manifest.version)?))
+        }
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_yaml_and_toml() {
+        let yaml = "packages:\n  - source: file://./example-package\n    version: \"^0.1\"\n  - id: org.hermione.other\n";
+        let toml = "[[packages]]\nsource = \"file://./example-package\"\nversion = \"^0.1\"\n\n[[packages]]\nid = \"org.hermione.other\"\n";
+        let from_yaml = Hermfile::from_yaml(yaml).expect("Unable to parse YAML in test");
+        let from_toml = Hermfile::from_toml(toml).expect("Unable to parse TOML in test");
+        assert_eq!(from_yaml, from_toml);
+        assert_eq!(2, from_yaml.packages.len());
+        assert_eq!(None, from_yaml.packages[1].source);
+    }
+
+    #[test]
+    fn test_round_trip_toml() {
+        let hermfile = Hermfile {
+            packages: vec![HermfileEntry {
+                source: Some(String::from("https://example.com/dots.hpkg")),
+                id: Some(String::from("org.example.dots")),
+                version: Some(String::from("=1.0.0")),
+                integrity: Some(String::from(
+                    "sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=",
+                )),
+            }],
+        };
+        let toml = hermfile
+            .to_string_for_path(Path::new("Hermfile.lock.toml"))
+            .expect("Unable to write TOML in test");
+        assert_eq!(
+            hermfile,
+            Hermfile::from_toml(&toml).expect("Unable to parse TOML in test")
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let yaml = "packages:\n  - source: file://./dots\n    features: [work]\n";
+        let toml = "[[packages]]\nsource = \"file://./dots\"\n\n[packages.variables]\nemail = \"me@example.com\"\n";
+        let yaml_error = Hermfile::from_yaml(yaml).expect_err("Parsed unknown key in test");
+        let toml_error = Hermfile::from_toml(toml).expect_err("Parsed unknown key in test");
+        assert!(yaml_error.to_string().contains("features"));
+        assert!(toml_error.to_string().contains("variables"));
+    }
+
+    #[test]
+    fn test_entry_needs_source_or_id() {
+        let hermfile = Hermfile {
+            packages: vec![HermfileEntry {
+                source: None,
+                id: None,
+                version: Some(String::from("^1")),
+                integrity: None,
+            }],
+        };
+        assert!(hermfile.check().is_err());
+    }
+}
@@ -0,0 +1,349 @@
+use chrono::Local;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::events::Event;
+use crate::installed_package::InstalledPackage;
+use crate::package_service::PackageService;
+use crate::versions::Versions;
+
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+/// Kinds of operations recorded in the history.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryOperation {
+    Install,
+    Remove,
+    Upgrade,
+    Repair,
+    Rollback,
+    Undo,
+}
+
+impl fmt::Display for HistoryOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HistoryOperation::Install => "install",
+            HistoryOperation::Remove => "remove",
+            HistoryOperation::Upgrade => "upgrade",
+            HistoryOperation::Repair => "repair",
+            HistoryOperation::Rollback => "rollback",
+            HistoryOperation::Undo => "undo",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One line of the history log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Position in the history, starting at 1.
+    pub id: usize,
+    /// RFC 3339 timestamp of when the operation finished.
+    pub timestamp: String,
+    pub operation: HistoryOperation,
+    /// Id of the package the operation was about.
+    pub package: String,
+    /// Version installed before the operation.
+    pub from_version: Option<String>,
+    /// Version installed after the operation.
+    pub to_version: Option<String>,
+    /// Source of the version installed before the operation.
+    pub from_source: Option<String>,
+    /// Source of the version installed after the operation.
+    pub to_source: Option<String>,
+    /// Package directory and mapping outputs the operation touched.
+    #[serde(default)]
+    pub paths: Vec<PathBuf>,
+    /// Whether the operation succeeded.
+    pub ok: bool,
+    /// Why the operation failed.
+    pub error: Option<String>,
+    /// Id of the entry an undo reverted.
+    pub undoes: Option<usize>,
+}
+
+impl fmt::Display for HistoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {} {} {}",
+            self.id, self.timestamp, self.operation, self.package
+        )?;
+        match (&self.from_version, &self.to_version) {
+            (Some(from), Some(to)) if from != to => write!(f, " {} -> {}", from, to)?,
+            (_, Some(version)) | (Some(version), None) => write!(f, " {}", version)?,
+            (None, None) => {}
+        }
+        if let Some(undoes) = self.undoes {
+            write!(f, " (undid #{})", undoes)?;
+        }
+        match &self.error {
+            Some(error) => write!(f, " <red>failed</>: {}", error),
+            None => write!(f, " <green>ok</>"),
+        }
+    }
+}
+
+impl HistoryEntry {
+    /// Returns an entry for an operation on a package, to be filled in as it runs.
+    pub fn new(operation: HistoryOperation, package: &str) -> Self {
+        Self {
+            id: 0,
+            timestamp: String::new(),
+            operation,
+            package: String::from(package),
+            from_version: None,
+            to_version: None,
+            from_source: None,
+            to_source: None,
+            paths: Vec::new(),
+            ok: false,
+            error: None,
+            undoes: None,
+        }
+    }
+
+    /// Consumes the entry and records the state of a package before the operation.
+    pub fn before(self, installed_package: &InstalledPackage) -> Self {
+        Self {
+            from_version: Some(installed_package.manifest.version.clone()),
+            from_source: installed_package.source(),
+            paths: installed_package.touched_paths(),
+            ..self
+        }
+    }
+
+    /// Consumes the entry and records the state of a package after the operation.
+    /// The package id replaces whatever the entry was created with, e.g. the source of an install.
+    pub fn after(self, installed_package: &InstalledPackage) -> Self {
+        let mut paths = self.paths;
+        for path in installed_package.touched_paths() {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        Self {
+            package: installed_package.manifest.id.clone(),
+            to_version: Some(installed_package.manifest.version.clone()),
+            to_source: installed_package.source(),
+            paths,
+            ..self
+        }
+    }
+}
+
+/// History is the append-only log of what Hermione did, kept as JSON lines in the install directory.
+pub struct History {
+    package_service: PackageService,
+}
+
+impl History {
+    pub fn new(package_service: &PackageService) -> Self {
+        Self {
+            package_service: package_service.clone(),
+        }
+    }
+
+    /// Returns the path of the history log.
+    pub fn path(&self) -> PathBuf {
+        self.package_service.install_dir().join(HISTORY_FILE_NAME)
+    }
+
+    /// Returns every recorded entry, oldest first.
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        let path = self.path();
+        if !path.is_file() {
+            return Ok(Vec::new());
+        }
+        fs::read_to_string(&path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .wrap_err_with(|| format!("Could not parse history {}", path.display()))
+            })
+            .collect()
+    }
+
+    /// Runs an operation and records its outcome. Nothing is recorded when dry running.
+    ///
+    /// ### Arguments
+    ///
+    /// * entry - Entry describing the operation, as known before it runs.
+    /// * operation - Closure doing the work, returning the entry completed with what it changed.
+    ///
+    /// Returns the result of the operation.
+    pub fn track<T, F>(&self, entry: HistoryEntry, operation: F) -> Result<T>
+    where
+        F: FnOnce(HistoryEntry) -> Result<(T, HistoryEntry)>,
+    {
+        let fallback = entry.clone();
+        let (result, entry) = match operation(entry) {
+            Ok((value, entry)) => (Ok(value), HistoryEntry { ok: true, ..entry }),
+            Err(e) => {
+                let error = Some(format!("{:#}", e));
+                (Err(e), HistoryEntry { error, ..fallback })
+            }
+        };
+        if let Err(e) = self.append(entry) {
+            self.package_service.events.emit(Event::warning(format!(
+                "Unable to record history in {}: {}",
+                self.path().display(),
+                e
+            )));
+        }
+        result
+    }
+
+    /// Appends an entry, numbering and timestamping it.
+    fn append(&self, entry: HistoryEntry) -> Result<HistoryEntry> {
+        if self.package_service.plan.is_some() {
+            return Ok(entry);
+        }
+        let entry = HistoryEntry {
+            id: self.entries()?.len() + 1,
+            timestamp: Local::now().to_rfc3339(),
+            ..entry
+        };
+        let path = self.path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        Ok(entry)
+    }
+
+    /// Returns the most recent successful operation which has not been undone yet.
+    /// Repairs are skipped since they only restore what the package already declares.
+    pub fn last_undoable(&self) -> Result<Option<HistoryEntry>> {
+        let entries = self.entries()?;
+        let undone = entries
+            .iter()
+            .filter_map(|entry| entry.undoes)
+            .collect::<HashSet<_>>();
+        Ok(entries.into_iter().rev().find(|entry| {
+            entry.ok
+                && !undone.contains(&entry.id)
+                && entry.operation != HistoryOperation::Undo
+                && entry.operation != HistoryOperation::Repair
+        }))
+    }
+
+    /// Returns true when the version a package had before the entry is still kept
+    /// and the package is installed, so it can be switched back to locally.
+    fn is_kept(package_service: &PackageService, entry: &HistoryEntry) -> bool {
+        let versions = Versions::new(package_service, &entry.package);
+        match (&entry.from_version, versions.current()) {
+            (Some(from_version), Some(_)) => versions
+                .installed()
+                .map(|kept| kept.contains(from_version))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// Reverts the most recent operation using the recorded state: installs are removed,
+    /// removed packages are reinstalled and upgrades and rollbacks go back to the previous
+    /// version, which is downloaded again from its source when it is no longer kept.
+    ///
+    /// Returns the entry which was undone as a Result.
+    pub fn undo(&self) -> Result<HistoryEntry> {
+        let target = self
+            .last_undoable()?
+            .ok_or_else(|| eyre!("Nothing to undo"))?;
+        let events = &self.package_service.events;
+        events.emit(Event::info(format!("Undoing {}", target)));
+
+        let entry = HistoryEntry {
+            undoes: Some(target.id),
+            ..HistoryEntry::new(HistoryOperation::Undo, &target.package)
+        };
+        self.track(entry, |entry| {
+            let package_service = self.package_service.clone();
+            let entry = match target.operation {
+                HistoryOperation::Install => {
+                    let installed_package =
+                        package_service.get_installed_package(target.package.clone())?;
+                    let entry = entry.before(&installed_package);
+                    installed_package.remove_untracked()?;
+                    entry
+                }
+                HistoryOperation::Upgrade | HistoryOperation::Rollback
+                    if Self::is_kept(&package_service, &target) =>
+                {
+                    let installed_package =
+                        package_service.get_installed_package(target.package.clone())?;
+                    let entry = entry.before(&installed_package);
+                    let installed_package =
+                        installed_package.rollback_untracked(target.from_version.clone())?;
+                    entry.after(&installed_package)
+                }
+                HistoryOperation::Remove | HistoryOperation::Upgrade => {
+                    let source = target.from_source.clone().ok_or_else(|| {
+                        eyre!(
+                            "History does not say where {} {} came from",
+                            target.package,
+                            target.from_version.clone().unwrap_or_default()
+                        )
+                    })?;
+                    let entry = match package_service
+                        .clone()
+                        .get_installed_package(target.package.clone())
+                    {
+                        Ok(installed_package) => {
+                            let entry = entry.before(&installed_package);
+                            installed_package.uninstall()?;
+                            entry
+                        }
+                        Err(_) => entry,
+                    };
+                    let installed_package = package_service.download(source)?.install()?;
+                    if target.from_version.as_ref() != Some(&installed_package.manifest.version) {
+                        events.emit(Event::warning(format!(
+                            "Reinstalled {} {} although {} was recorded",
+                            installed_package.manifest.id,
+                            installed_package.manifest.version,
+                            target.from_version.clone().unwrap_or_default()
+                        )));
+                    }
+                    entry.after(&installed_package)
+                }
+                HistoryOperation::Rollback | HistoryOperation::Repair | HistoryOperation::Undo => {
+                    return Err(eyre!("Unable to undo a {}", target.operation))
+                }
+            };
+            Ok(((), entry))
+        })?;
+        Ok(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_display() {
+        let entry = HistoryEntry {
+            id: 3,
+            timestamp: String::from("2020-01-01T00:00:00+00:00"),
+            from_version: Some(String::from("0.1.0")),
+            to_version: Some(String::from("0.2.0")),
+            ok: true,
+            ..HistoryEntry::new(HistoryOperation::Upgrade, "org.example.dots")
+        };
+        assert_eq!(
+            "#3 2020-01-01T00:00:00+00:00 upgrade org.example.dots 0.1.0 -> 0.2.0 <green>ok</>",
+            entry.to_string()
+        );
+    }
+}
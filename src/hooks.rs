@@ -1,61 +1,145 @@
-use color_eyre::eyre::{eyre, Result};
+use color_eyre::eyre::Result;
+use duckscript::types::command::{Command, CommandResult};
 use duckscript::{runner, types};
-use paris::Logger;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+use std::sync::Arc;
+
+use crate::error::HermioneError;
+use crate::events::{Event, EventSink};
+use crate::package_service::PackageService;
+use crate::plan::PlannedOperation;
+
+/// Optional Duckscript run around installing and removing a package.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Hooks {
+    /// Runs before the mappings are installed.
     pub pre_install: Option<String>,
+    /// Runs after the mappings are installed.
     pub post_install: Option<String>,
+    /// Runs before the mappings are removed.
     pub pre_remove: Option<String>,
+    /// Runs after the mappings are removed.
     pub post_remove: Option<String>,
 }
 
 impl Hooks {
-    pub fn execute_pre_install(&self) -> Result<()> {
-        Hooks::execute("pre_install", &self.pre_install)
+    /// Returns the names of the hooks which have a script.
+    pub fn defined(&self) -> Vec<String> {
+        [
+            ("pre_install", &self.pre_install),
+            ("post_install", &self.post_install),
+            ("pre_remove", &self.pre_remove),
+            ("post_remove", &self.post_remove),
+        ]
+        .iter()
+        .filter(|(_, script)| script.is_some())
+        .map(|(name, _)| String::from(*name))
+        .collect()
     }
-    pub fn execute_post_install(&self) -> Result<()> {
-        Hooks::execute("post_install", &self.post_install)
+
+    pub fn execute_pre_install(&self, package_service: &PackageService) -> Result<()> {
+        Hooks::execute(package_service, "pre_install", &self.pre_install)
+    }
+    pub fn execute_post_install(&self, package_service: &PackageService) -> Result<()> {
+        Hooks::execute(package_service, "post_install", &self.post_install)
     }
 
-    pub fn execute_pre_remove(&self) -> Result<()> {
-        Hooks::execute("pre_remove", &self.pre_remove)
+    pub fn execute_pre_remove(&self, package_service: &PackageService) -> Result<()> {
+        Hooks::execute(package_service, "pre_remove", &self.pre_remove)
     }
-    pub fn execute_post_remove(&self) -> Result<()> {
-        Hooks::execute("post_remove", &self.post_remove)
+    pub fn execute_post_remove(&self, package_service: &PackageService) -> Result<()> {
+        Hooks::execute(package_service, "post_remove", &self.post_remove)
     }
 
-    pub fn execute(hook_name: &str, script_string: &Option<String>) -> Result<()> {
+    pub fn execute(
+        package_service: &PackageService,
+        hook_name: &str,
+        script_string: &Option<String>,
+    ) -> Result<()> {
         match script_string {
-            Some(f) => {
-                let mut logger = Logger::new();
-                logger.info(format!("Initiated {} hook", hook_name));
-                logger
-                    .info("Loading Duckscript sdk into context")
-                    .newline(1);
-
-                let mut context = types::runtime::Context::new();
-                match duckscriptsdk::load(&mut context.commands) {
-                    Ok(_) => match runner::run_script(f.as_str(), context) {
-                        Ok(_) => {
-                            logger.newline(1).success("Finished running Duckscript");
-                            Ok(())
-                        }
-                        Err(e) => Err(eyre!(
-                            "Failed to run Duckscript in {} hook: {}",
-                            hook_name,
-                            e
-                        )),
-                    },
-                    Err(e) => Err(eyre!(
-                        "Failed to run Duckscript in {} hook: {}",
-                        hook_name,
-                        e
-                    )),
-                }
-            }
+            Some(f) => package_service.perform(
+                PlannedOperation::RunHook {
+                    hook: String::from(hook_name),
+                },
+                || {
+                    package_service.events.emit(Event::HookStarted {
+                        hook: String::from(hook_name),
+                    });
+                    Hooks::run(hook_name, f, package_service.events.clone())?;
+                    package_service.events.emit(Event::HookFinished {
+                        hook: String::from(hook_name),
+                    });
+                    Ok(())
+                },
+            ),
             None => Ok(()),
         }
     }
+
+    /// Runs a Duckscript hook.
+    ///
+    /// ### Arguments
+    ///
+    /// * hook_name - Name of the hook, used in errors.
+    /// * f - Duckscript source.
+    /// * events - Sink receiving what the script echoes.
+    ///
+    /// Returns an empty Result.
+    fn run(hook_name: &str, f: &str, events: Arc<dyn EventSink>) -> Result<()> {
+        let mut context = types::runtime::Context::new();
+        let loaded = duckscriptsdk::load(&mut context.commands).and_then(|_| {
+            context.commands.remove("echo");
+            context.commands.set(Box::new(EventEcho {
+                hook: String::from(hook_name),
+                events,
+            }))
+        });
+        match loaded {
+            Ok(_) => match runner::run_script(f, context) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(HermioneError::Hook(format!(
+                    "Failed to run Duckscript in {} hook: {}",
+                    hook_name, e
+                ))
+                .into()),
+            },
+            Err(e) => Err(HermioneError::Hook(format!(
+                "Failed to run Duckscript in {} hook: {}",
+                hook_name, e
+            ))
+            .into()),
+        }
+    }
+}
+
+/// Replacement for the duckscript `echo` command which reports
+/// what hooks print as events instead of writing to stdout.
+#[derive(Clone)]
+struct EventEcho {
+    hook: String,
+    events: Arc<dyn EventSink>,
+}
+
+impl Command for EventEcho {
+    fn name(&self) -> String {
+        String::from("std::Echo")
+    }
+
+    fn aliases(&self) -> Vec<String> {
+        vec![String::from("echo")]
+    }
+
+    fn clone_and_box(&self) -> Box<dyn Command> {
+        Box::new(self.clone())
+    }
+
+    fn run(&self, arguments: Vec<String>) -> CommandResult {
+        self.events.emit(Event::HookOutput {
+            hook: self.hook.clone(),
+            line: arguments.join(" "),
+        });
+        CommandResult::Continue(Some(arguments.len().to_string()))
+    }
 }
@@ -0,0 +1,125 @@
+use serde::Serialize;
+
+use std::fmt;
+
+use crate::manifest::Manifest;
+
+/// Kind of an entry in a package archive.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+    Other,
+}
+
+/// One entry of a package archive.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ArchiveEntry {
+    /// Path of the entry inside the archive.
+    pub path: String,
+    pub kind: EntryKind,
+    /// Size in bytes.
+    pub size: u64,
+    /// Unix permission bits.
+    pub mode: u32,
+}
+
+impl fmt::Display for ArchiveEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            EntryKind::File => "-",
+            EntryKind::Directory => "d",
+            EntryKind::Symlink => "l",
+            EntryKind::Other => "?",
+        };
+        write!(
+            f,
+            "{}{:04o} {:>10} {}",
+            kind, self.mode, self.size, self.path
+        )
+    }
+}
+
+/// How the input of a mapping compares to its recorded integrity.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityState {
+    /// The archived file matches the recorded integrity.
+    Ok,
+    /// The archived file does not match the recorded integrity.
+    Mismatch,
+    /// The manifest records no integrity for the mapping.
+    Unrecorded,
+    /// The archive does not contain the mapping's input.
+    Missing,
+}
+
+impl IntegrityState {
+    /// Returns true when the archive can not be trusted to install this mapping.
+    pub fn is_failure(self) -> bool {
+        self == IntegrityState::Mismatch || self == IntegrityState::Missing
+    }
+}
+
+impl fmt::Display for IntegrityState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            IntegrityState::Ok => "ok",
+            IntegrityState::Mismatch => "mismatch",
+            IntegrityState::Unrecorded => "unrecorded",
+            IntegrityState::Missing => "missing",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Integrity check of one mapping input, or included file, against the archive contents.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MappingIntegrity {
+    /// Input path of the mapping, or path of the included file.
+    pub input: String,
+    pub integrity: Option<String>,
+    pub state: IntegrityState,
+}
+
+impl MappingIntegrity {
+    /// Returns String print out of the check.
+    pub fn display_line(&self) -> String {
+        let state = match self.state {
+            IntegrityState::Ok => format!("<green>{}</>", self.state),
+            IntegrityState::Unrecorded => format!("<yellow>{}</>", self.state),
+            _ => format!("<red>{}</>", self.state),
+        };
+        format!("{} {}", state, self.input)
+    }
+}
+
+/// Everything found inside a package archive, read without unpacking it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Inspection {
+    /// Every archive entry in archive order.
+    pub entries: Vec<ArchiveEntry>,
+    /// Manifest embedded in the archive.
+    pub manifest: Manifest,
+    /// Manifest exactly as it is stored in the archive, comments and unknown keys included.
+    pub manifest_text: String,
+    /// Integrity check of every mapping input.
+    pub mappings: Vec<MappingIntegrity>,
+    /// Integrity check of every included file the manifest records.
+    pub files: Vec<MappingIntegrity>,
+    /// Files in the archive which neither a mapping nor the recorded included files refer to.
+    pub unreferenced: Vec<String>,
+}
+
+impl Inspection {
+    /// Returns the mapping and included file checks which failed.
+    pub fn failures(&self) -> Vec<&MappingIntegrity> {
+        self.mappings
+            .iter()
+            .chain(self.files.iter())
+            .filter(|mapping| mapping.state.is_failure())
+            .collect()
+    }
+}
@@ -1,12 +1,21 @@
-use color_eyre::eyre::Result;
-use paris::Logger;
+use color_eyre::eyre::{eyre, Result};
+use semver::Version;
 
 use std::fs;
 use std::path::PathBuf;
 
+use crate::backup::Backup;
+use crate::conflict_strategy::ConflictStrategy;
 use crate::downloaded_package::DownloadedPackage;
+use crate::error::HermioneError;
+use crate::events::Event;
+use crate::history::{History, HistoryEntry, HistoryOperation};
 use crate::manifest::Manifest;
+use crate::mapping_check::{MappingCheck, MappingState};
 use crate::package_service::PackageService;
+use crate::plan::PlannedOperation;
+use crate::receipt::Receipt;
+use crate::versions::Versions;
 
 /// Installed state of a package.
 /// This means that a package has been downloaded and the
@@ -21,16 +30,16 @@ pub struct InstalledPackage {
 }
 
 impl InstalledPackage {
-    /// Removes a given package.
-    /// First we try to remove all the files it installed in the
-    /// manifest before we remove the package directory itself.
+    /// Unlinks the files this package installed.
+    /// The version directory is kept so it can be rolled back to, unless the package
+    /// predates versioned directories in which case its directory is removed.
     pub fn uninstall(&self) -> Result<DownloadedPackage> {
-        let manifest_path = self.local_path.join(Manifest::manifest_file_name());
-        let mut logger = Logger::new();
-        logger.info(format!(
+        let manifest_path = Manifest::path_in(&self.local_path)?;
+        let events = &self.package_service.events;
+        events.emit(Event::info(format!(
             "Unlinking files defined in Manifest file: {}",
             manifest_path.display(),
-        ));
+        )));
 
         let manifest = Manifest::new_from_path(manifest_path)?;
 
@@ -38,16 +47,23 @@ impl InstalledPackage {
             if mapping_definition.valid_platform_family() {
                 let mapping = mapping_definition
                     .render_file_mapping(&self.package_service, self.local_path.clone())?;
-                logger.indent(1).log(mapping.uninstall()?);
+                mapping.uninstall(&self.package_service)?;
             }
         }
-        logger.success("Successfully unlinked files");
+        events.emit(Event::success("Successfully unlinked files"));
 
-        fs::remove_dir_all(&self.local_path)?;
-        logger.success(format!(
-            "Successfully removed installed package {}",
-            &manifest.name,
-        ));
+        if Versions::new(&self.package_service, &manifest.id).is_legacy() {
+            self.package_service.perform(
+                PlannedOperation::Remove {
+                    path: self.local_path.clone(),
+                },
+                || Ok(fs::remove_dir_all(&self.local_path)?),
+            )?;
+            events.emit(Event::success(format!(
+                "Successfully removed installed package {}",
+                &manifest.name,
+            )));
+        }
 
         let downloaded_path_buf = self.package_service.download_dir().join(&manifest.id);
 
@@ -57,27 +73,304 @@ impl InstalledPackage {
         })
     }
 
+    /// Returns the source recorded when this package was downloaded, if any.
+    pub fn source(&self) -> Option<String> {
+        Receipt::load(&self.local_path)
+            .ok()
+            .map(|receipt| receipt.source)
+    }
+
+    /// Returns the package directory followed by the mapping outputs this package links.
+    pub fn touched_paths(&self) -> Vec<PathBuf> {
+        let mut paths = vec![self.local_path.clone()];
+        if let Ok(checks) = self.verify() {
+            paths.extend(
+                checks
+                    .iter()
+                    .filter(|check| check.state != MappingState::Skipped)
+                    .map(|check| check.mapping.output().to_path_buf()),
+            );
+        }
+        paths
+    }
+
+    /// Compares every mapping of this package against the system.
+    ///
+    /// Returns a vector of MappingCheck as a Result.
+    pub fn verify(&self) -> Result<Vec<MappingCheck>> {
+        let skipped = Receipt::load(&self.local_path)
+            .map(|receipt| receipt.skipped)
+            .unwrap_or_default();
+        self.manifest
+            .mappings
+            .iter()
+            .filter(|mapping_definition| mapping_definition.valid_platform_family())
+            .map(|mapping_definition| {
+                let mapping = mapping_definition
+                    .clone()
+                    .render_file_mapping(&self.package_service, self.local_path.clone())?;
+                let is_skipped = skipped.iter().any(|output| output == mapping.output());
+                MappingCheck::new(mapping_definition.clone(), mapping, is_skipped)
+            })
+            .collect()
+    }
+
+    /// Restores drifted mappings of this package.
+    /// Package files which no longer match their integrity are fetched again from the recorded source,
+    /// missing links are re-created and files which took the place of a link are backed up before relinking.
+    ///
+    /// ### Arguments
+    ///
+    /// * run_hooks - Run the install hooks around the repair.
+    ///
+    /// Returns the number of repaired mappings as a Result.
+    pub fn repair(&self, run_hooks: bool) -> Result<usize> {
+        let entry = HistoryEntry::new(HistoryOperation::Repair, &self.manifest.id).before(self);
+        History::new(&self.package_service).track(entry, |entry| {
+            let repaired = self.repair_untracked(run_hooks)?;
+            Ok((repaired, entry.after(self)))
+        })
+    }
+
+    fn repair_untracked(&self, run_hooks: bool) -> Result<usize> {
+        let events = &self.package_service.events;
+        let checks = self.verify()?;
+        let repaired = checks.iter().filter(|check| check.state.is_drift()).count();
+        let modified = checks
+            .iter()
+            .filter(|check| check.state == MappingState::Modified)
+            .collect::<Vec<_>>();
+
+        if !modified.is_empty() {
+            let receipt = Receipt::load(&self.local_path)?;
+            events.emit(Event::info(format!(
+                "Fetching package contents from {}",
+                receipt.source
+            )));
+            let downloaded_package = self.package_service.clone().download(receipt.source)?;
+            for check in modified {
+                if !check
+                    .definition
+                    .verify_integrity(downloaded_package.local_path.clone())?
+                {
+                    return Err(HermioneError::Integrity(format!(
+                        "Source no longer provides {} matching the installed integrity",
+                        check.definition.i
+                    ))
+                    .into());
+                }
+                let fetched_path = downloaded_package.local_path.join(&check.definition.i);
+                self.package_service.perform(
+                    PlannedOperation::Copy {
+                        from: fetched_path.clone(),
+                        to: check.mapping.input().to_path_buf(),
+                    },
+                    || {
+                        fs::copy(&fetched_path, check.mapping.input())?;
+                        Ok(())
+                    },
+                )?;
+                events.emit(Event::detail(format!(
+                    "<green>Restored</> {}",
+                    check.mapping.input().display()
+                )));
+            }
+        }
+
+        let drifted = self
+            .verify()?
+            .into_iter()
+            .filter(|check| check.state.is_drift())
+            .collect::<Vec<_>>();
+        if drifted.is_empty() {
+            return Ok(repaired);
+        }
+
+        let hooks = self.manifest.hooks.as_ref().filter(|_| run_hooks);
+        if let Some(hooks) = hooks {
+            hooks.execute_pre_install(&self.package_service)?;
+        }
+        for check in drifted {
+            let mapping = match check.state {
+                MappingState::Missing => check.mapping,
+                MappingState::Replaced | MappingState::Foreign => {
+                    check.mapping.with_on_conflict(ConflictStrategy::Backup)
+                }
+                MappingState::Ok | MappingState::Modified | MappingState::Skipped => {
+                    return Err(eyre!(
+                        "Unable to repair {}",
+                        check.mapping.output().display()
+                    ))
+                }
+            };
+            mapping.install(&self.package_service, &self.manifest.id)?;
+        }
+        if let Some(hooks) = hooks {
+            hooks.execute_post_install(&self.package_service)?;
+        }
+
+        Ok(repaired)
+    }
+
+    /// Switches back to a kept version of this package without downloading anything.
+    ///
+    /// ### Arguments
+    ///
+    /// * version - Version to go back to, defaults to the newest kept version older than the current one.
+    ///
+    /// Returns the InstalledPackage of the activated version as a Result.
+    pub fn rollback(self, version: Option<String>) -> Result<InstalledPackage> {
+        let entry = HistoryEntry::new(HistoryOperation::Rollback, &self.manifest.id).before(&self);
+        History::new(&self.package_service.clone()).track(entry, |entry| {
+            let installed_package = self.rollback_untracked(version)?;
+            let entry = entry.after(&installed_package);
+            Ok((installed_package, entry))
+        })
+    }
+
+    /// Switches to a kept version without recording it in the history.
+    pub(crate) fn rollback_untracked(self, version: Option<String>) -> Result<InstalledPackage> {
+        let versions = Versions::new(&self.package_service, &self.manifest.id);
+        let kept = versions.installed()?;
+        let current = Version::parse(&self.manifest.version)?;
+        let target = match version {
+            Some(version) => version,
+            None => kept
+                .iter()
+                .rev()
+                .find(|kept_version| {
+                    Version::parse(kept_version)
+                        .map(|kept_version| kept_version < current)
+                        .unwrap_or(false)
+                })
+                .cloned()
+                .ok_or_else(|| {
+                    eyre!(
+                        "No version of {} older than {} is kept",
+                        self.manifest.id,
+                        self.manifest.version
+                    )
+                })?,
+        };
+        if target == self.manifest.version {
+            return Err(eyre!(
+                "{} {} is already the current version",
+                self.manifest.id,
+                target
+            ));
+        }
+        if !kept.contains(&target) {
+            return Err(HermioneError::NotInstalled(format!(
+                "Version {} of {} is not kept, kept versions are: {}",
+                target,
+                self.manifest.id,
+                kept.join(", ")
+            ))
+            .into());
+        }
+
+        self.package_service.events.emit(Event::info(format!(
+            "Rolling {} back from {} to {}",
+            self.manifest.id, self.manifest.version, target
+        )));
+        self.replace_with(DownloadedPackage {
+            local_path: versions.path(&target),
+            package_service: self.package_service.clone(),
+        })
+    }
+
+    /// Unlinks this package and installs `replacement` in its place.
+    /// When the replacement fails to install, this version is restored before the error is returned.
+    ///
+    /// Returns the InstalledPackage of the replacement as a Result.
+    pub fn replace_with(&self, replacement: DownloadedPackage) -> Result<InstalledPackage> {
+        self.uninstall()?;
+        let installed = replacement.install();
+        if installed.is_err() {
+            let events = &self.package_service.events;
+            events.emit(Event::info(format!(
+                "Reinstalling {} {}",
+                self.manifest.id, self.manifest.version
+            )));
+            if let Err(restore_error) = self.restore() {
+                events.emit(Event::error(format!(
+                    "Unable to reinstall {} {}: {:#}",
+                    self.manifest.id, self.manifest.version, restore_error
+                )));
+            }
+        }
+        installed
+    }
+
+    /// Makes this version current again and relinks its files, e.g. after it was uninstalled
+    /// to make way for a version which then failed to install.
+    /// Packages predating versioned directories are gone once uninstalled and can not be restored.
+    ///
+    /// Returns the restored InstalledPackage as a Result.
+    pub fn restore(&self) -> Result<InstalledPackage> {
+        if !self.local_path.is_dir() {
+            return Err(eyre!(
+                "{} {} is no longer kept in {}",
+                self.manifest.id,
+                self.manifest.version,
+                self.local_path.display()
+            ));
+        }
+        Versions::new(&self.package_service, &self.manifest.id)
+            .set_current(&self.manifest.version)?;
+        DownloadedPackage {
+            local_path: self.local_path.clone(),
+            package_service: self.package_service.clone(),
+        }
+        .install()
+    }
+
     /// Removed the package directory it self after the files of this
     /// package have been successfully uninstalled.
+    /// Any files backed up when this package was installed are restored.
     pub fn remove(self) -> Result<bool> {
-        let manifest_path = self.local_path.join(Manifest::manifest_file_name());
-        let manifest = Manifest::new_from_path(manifest_path)?;
+        let history = History::new(&self.package_service);
+        let entry = HistoryEntry::new(HistoryOperation::Remove, &self.manifest.id).before(&self);
+        history.track(entry, |entry| Ok((self.remove_untracked()?, entry)))
+    }
+
+    /// Removes the package without recording it in the history.
+    pub(crate) fn remove_untracked(self) -> Result<bool> {
+        let manifest = Manifest::new_from_dir(&self.local_path)?;
 
         let downloaded_package = self.uninstall()?;
-        let mut logger = Logger::new();
+        let events = &self.package_service.events;
+        let package_root = Versions::new(&self.package_service, &manifest.id)
+            .root()
+            .to_path_buf();
+        if package_root.exists() {
+            self.package_service.perform(
+                PlannedOperation::Remove {
+                    path: package_root.clone(),
+                },
+                || Ok(fs::remove_dir_all(&package_root)?),
+            )?;
+            events.emit(Event::success(format!(
+                "Successfully removed installed package {}",
+                &manifest.name,
+            )));
+        }
+        for backup in Backup::list_for_package(&self.package_service.backup_dir(), &manifest.id)? {
+            self.package_service.restore_backup(backup)?;
+        }
         match &manifest.hooks {
-            Some(hooks) => hooks.execute_pre_remove()?,
+            Some(hooks) => hooks.execute_pre_remove(&self.package_service)?,
             None => {
-                logger.log("No pre_remove hook");
+                events.emit(Event::detail("No pre_remove hook"));
             }
         };
 
         downloaded_package.remove()?;
 
         match manifest.hooks {
-            Some(hooks) => hooks.execute_post_remove()?,
+            Some(hooks) => hooks.execute_post_remove(&self.package_service)?,
             None => {
-                logger.log("No post_remove hook");
+                events.emit(Event::detail("No post_remove hook"));
             }
         };
 
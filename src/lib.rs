@@ -0,0 +1,61 @@
+#![forbid(unsafe_code)]
+
+//! Hermione is a package manager for your config files.
+//!
+//! This crate powers the `herm` command line tool and can be used directly
+//! to load manifests, download, install, list, remove and pack packages.
+//! Progress is reported to an [`events::EventSink`], which is silent unless you pick one.
+//!
+//! ```no_run
+//! use hermione::PackageService;
+//!
+//! # fn main() -> color_eyre::eyre::Result<()> {
+//! let package_service = PackageService::new()?;
+//! package_service.init()?;
+//! for installed_package in package_service.list_installed_packages()? {
+//!     println!("{} @ {}", installed_package.manifest.id, installed_package.manifest.version);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod adopter;
+pub mod backup;
+#[allow(dead_code)]
+mod config;
+pub mod conflict_strategy;
+pub mod downloaded_package;
+pub mod downloader;
+pub mod error;
+pub mod events;
+pub mod file_mapping;
+pub mod file_mapping_definition;
+pub mod hermfile;
+pub mod history;
+pub mod hooks;
+pub mod inspection;
+pub mod installed_package;
+pub mod lint;
+pub mod manifest;
+pub mod mapping_check;
+pub mod migration;
+pub mod outdated;
+pub mod output;
+pub mod package_info;
+pub mod package_service;
+pub mod packer;
+pub mod pins;
+pub mod plan;
+pub mod receipt;
+#[allow(dead_code)]
+mod repositories;
+pub mod scaffold;
+pub mod search;
+pub mod versions;
+
+pub use crate::downloaded_package::DownloadedPackage;
+pub use crate::error::HermioneError;
+pub use crate::installed_package::InstalledPackage;
+pub use crate::manifest::Manifest;
+pub use crate::package_service::PackageService;
+pub use crate::packer::Packer;
@@ -0,0 +1,313 @@
+use color_eyre::eyre::Result;
+use globset::GlobBuilder;
+use semver::Version;
+use serde::Serialize;
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::manifest::{Manifest, ManifestFormat};
+use crate::package_service::PackageService;
+
+/// Platform families a mapping can be limited to.
+const KNOWN_PLATFORMS: &[&str] = &["unix", "windows"];
+
+/// Something wrong with a package, pointing at where it was found.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LintProblem {
+    pub file: PathBuf,
+    /// Line in the file, starting at 1, when it could be located.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for LintProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file.display(), line, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+/// Checks a package directory and collects every problem instead of stopping at the first one.
+///
+/// ### Arguments
+///
+/// * package_service - Provides the facts mapping outputs are rendered with.
+/// * package_path - Directory containing the manifest.
+///
+/// Returns the problems found, empty for a clean package, as a Result.
+pub fn lint(package_service: &PackageService, package_path: &Path) -> Result<Vec<LintProblem>> {
+    let mut problems = Vec::new();
+    let manifest_path = match Manifest::path_in(package_path) {
+        Ok(manifest_path) => manifest_path,
+        Err(e) => {
+            problems.push(LintProblem {
+                file: package_path.to_path_buf(),
+                line: None,
+                message: format!("{}", e),
+            });
+            return Ok(problems);
+        }
+    };
+    let format = ManifestFormat::from_path(&manifest_path);
+    let mut problem = |line: Option<usize>, message: String| {
+        problems.push(LintProblem {
+            file: manifest_path.clone(),
+            line,
+            message,
+        })
+    };
+
+    let contents = match fs::read_to_string(&manifest_path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            problem(None, format!("Unable to read manifest: {}", e));
+            return Ok(problems);
+        }
+    };
+    let manifest = match parse(format, &contents) {
+        Ok(manifest) => manifest,
+        Err((line, message)) => {
+            problem(line, message);
+            return Ok(problems);
+        }
+    };
+    let lines = Lines::new(&contents);
+
+    if let Err(e) = Version::parse(&manifest.version) {
+        problem(
+            lines.find("version", 0),
+            format!("Invalid semver version {}: {}", manifest.version, e),
+        );
+    }
+    if !is_reverse_domain(&manifest.id) {
+        problem(
+            lines.find("id", 0),
+            format!(
+                "Id {} is not a reverse domain name like org.example.package",
+                manifest.id
+            ),
+        );
+    }
+
+    for pattern in &manifest.include {
+        if let Err(e) = GlobBuilder::new(pattern).literal_separator(true).build() {
+            problem(
+                lines.find("include", 0),
+                format!("Invalid include glob {}: {}", pattern, e),
+            );
+        }
+    }
+
+    let mut outputs: Vec<(usize, PathBuf, Option<String>)> = Vec::new();
+    for (index, definition) in manifest.mappings.iter().enumerate() {
+        let input_line = lines.find("i", index);
+        if !package_path.join(&definition.i).is_file() {
+            problem(
+                input_line,
+                format!("Mapping input {} does not exist", definition.i),
+            );
+        } else if definition.integrity.is_some()
+            && !definition
+                .verify_integrity(package_path.to_path_buf())
+                .unwrap_or(false)
+        {
+            problem(
+                lines.find_after("integrity", input_line),
+                format!("Integrity of {} does not match its contents", definition.i),
+            );
+        }
+
+        if let Some(platform) = &definition.platform {
+            if !KNOWN_PLATFORMS.contains(&platform.as_str()) {
+                problem(
+                    lines.find_after("platform", input_line),
+                    format!(
+                        "Unknown platform {}, expected one of {}",
+                        platform,
+                        KNOWN_PLATFORMS.join(", ")
+                    ),
+                );
+            }
+        }
+
+        let output_line = lines.find_after("o", input_line);
+        match definition
+            .clone()
+            .render_file_mapping(package_service, package_path.to_path_buf())
+        {
+            Ok(mapping) => {
+                let output = mapping.output().to_path_buf();
+                let collision = outputs.iter().find(|(_, other, other_platform)| {
+                    other == &output
+                        && (other_platform.is_none()
+                            || definition.platform.is_none()
+                            || other_platform == &definition.platform)
+                });
+                if let Some((other_index, _, _)) = collision {
+                    problem(
+                        output_line,
+                        format!(
+                            "Mapping output {} is also the output of mapping {}",
+                            output.display(),
+                            other_index + 1
+                        ),
+                    );
+                }
+                outputs.push((index, output, definition.platform.clone()));
+            }
+            Err(e) => problem(output_line, format!("{}", e)),
+        }
+    }
+
+    if let Some(hooks) = &manifest.hooks {
+        for (hook, script) in &[
+            ("pre_install", &hooks.pre_install),
+            ("post_install", &hooks.post_install),
+            ("pre_remove", &hooks.pre_remove),
+            ("post_remove", &hooks.post_remove),
+        ] {
+            if let Some(script) = script {
+                if let Err(e) = duckscript::parser::parse_text(script) {
+                    problem(
+                        lines.find(hook, 0),
+                        format!("{} hook is not valid Duckscript: {}", hook, e),
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Parses a manifest, returning the line of the syntax error on failure when the parser knows it.
+fn parse(format: ManifestFormat, contents: &str) -> Result<Manifest, (Option<usize>, String)> {
+    let message = |e: &dyn fmt::Display| format!("Could not parse manifest {}: {}", format, e);
+    match format {
+        ManifestFormat::Yaml => serde_yaml::from_str(contents)
+            .map_err(|e| (e.location().map(|location| location.line()), message(&e))),
+        ManifestFormat::Toml => toml::from_str(contents)
+            .map_err(|e| (e.line_col().map(|(line, _)| line + 1), message(&e))),
+        ManifestFormat::Json => {
+            serde_json::from_str(contents).map_err(|e| (Some(e.line()), message(&e)))
+        }
+    }
+}
+
+/// Returns true for ids like `org.example.package`: at least two dot separated labels
+/// of letters, digits, `-` and `_`, none of them empty or starting with `-`.
+fn is_reverse_domain(id: &str) -> bool {
+    let labels = id.split('.').collect::<Vec<_>>();
+    labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+/// Finds the lines of manifest keys so problems can point at them.
+/// This is a plain text search, good enough for manifests written one key per line,
+/// like the ones `herm new` and `herm init` write in every format.
+struct Lines {
+    keys: Vec<(usize, String)>,
+}
+
+impl Lines {
+    fn new(contents: &str) -> Self {
+        let keys = contents
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let line = line.trim_start().trim_start_matches("- ").trim_start();
+                line.find([':', '=']).map(|separator| {
+                    let key = line[..separator].trim().trim_matches('"');
+                    (index + 1, String::from(key))
+                })
+            })
+            .collect();
+        Self { keys }
+    }
+
+    /// Returns the line of the nth occurrence of a key.
+    fn find(&self, key: &str, nth: usize) -> Option<usize> {
+        self.keys
+            .iter()
+            .filter(|(_, found)| found == key)
+            .nth(nth)
+            .map(|(line, _)| *line)
+    }
+
+    /// Returns the line of the first occurrence of a key within the mapping starting at the given line.
+    fn find_after(&self, key: &str, start: Option<usize>) -> Option<usize> {
+        let start = start?;
+        self.keys
+            .iter()
+            .filter(|(line, _)| *line >= start)
+            .take_while(|(line, found)| *line == start || found != "i")
+            .find(|(_, found)| found == key)
+            .map(|(line, _)| *line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reverse_domain() {
+        assert!(is_reverse_domain("org.hermione.example-package"));
+        assert!(!is_reverse_domain("example"));
+        assert!(!is_reverse_domain("org..example"));
+        assert!(!is_reverse_domain("org.example package"));
+    }
+
+    #[test]
+    fn test_reports_every_problem_with_lines() {
+        let temp_dir = TempDir::new().expect("Unable to create temp dir in test");
+        fs::write(temp_dir.path().join("a.txt"), "a").expect("Unable to write file in test");
+        fs::write(
+            temp_dir.path().join("hermione.yml"),
+            "---
+name: Broken
+authors: []
+description: Broken package
+id: broken
+version: 0.1.0
+mappings:
+  - i: a.txt
+    o: /tmp/same.txt
+    integrity: sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=
+  - i: missing.txt
+    o: /tmp/same.txt
+    platform: beos
+hooks:
+  pre_install: \"echo \\\"unterminated\"
+",
+        )
+        .expect("Unable to write manifest in test");
+        let package_service =
+            PackageService::new().expect("Unable to instantiate PackageService in test");
+
+        let problems = lint(&package_service, temp_dir.path()).expect("Unable to lint in test");
+        let lines = problems
+            .iter()
+            .map(|problem| problem.line)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![Some(5), Some(10), Some(11), Some(13), Some(12), Some(15)],
+            lines,
+            "{:?}",
+            problems
+        );
+    }
+}
@@ -1,38 +1,104 @@
 #![forbid(unsafe_code)]
 
-use clap::{App, Arg, SubCommand};
+use clap::{App, Arg, ArgMatches, SubCommand};
 use color_eyre::eyre::{eyre, Result};
-use paris::Logger;
+
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
 
 mod action;
 mod actions;
-mod config;
-mod downloaded_package;
-mod downloader;
-mod file_mapping;
-mod file_mapping_definition;
-mod hooks;
-mod installed_package;
-mod manifest;
-mod package_service;
-mod packer;
-mod repositories;
-mod scaffold;
+
+use hermione::conflict_strategy::ConflictStrategy;
+use hermione::error::{self, HermioneError};
+use hermione::events::{Event, EventSink, JsonSink, PrettySink};
+use hermione::manifest::ManifestFormat;
+use hermione::output::{Output, OutputFormat};
+use hermione::package_service::PackageService;
+use hermione::plan::Plan;
 
 use crate::action::Action;
-use crate::package_service::PackageService;
 
-fn main() -> Result<()> {
+fn main() {
+    if let Err(report) = run() {
+        eprintln!("Error: {:?}", report);
+        process::exit(error::exit_code(&report));
+    }
+}
+
+/// Parses the command line and runs the requested subcommand.
+///
+/// Returns an empty Result.
+fn run() -> Result<()> {
     color_eyre::install()?;
     let matches = App::new("herm")
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
+        .arg(
+            Arg::with_name("dry_run")
+                .help("prints the planned changes (as text or json) without changing anything on disk")
+                .long("dry-run")
+                .global(true)
+                .takes_value(true)
+                .min_values(0)
+                .require_equals(true)
+                .value_name("FORMAT")
+                .possible_values(&["text", "json"]),
+        )
+        .arg(
+            Arg::with_name("output")
+                .help("prints a single JSON document to stdout and reports progress as JSON lines on stderr")
+                .long("output")
+                .global(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(OutputFormat::variants()),
+        )
+        .arg(
+            Arg::with_name("root")
+                .help("keeps installed packages, downloads and config below this directory instead of the OS defaults [env: HERMIONE_HOME]")
+                .long("root")
+                .global(true)
+                .takes_value(true)
+                .value_name("DIR"),
+        )
+        .arg(
+            Arg::with_name("keep_versions")
+                .help("number of previous versions of each package to keep for rollback [env: HERMIONE_KEEP_VERSIONS]")
+                .long("keep-versions")
+                .global(true)
+                .takes_value(true)
+                .value_name("COUNT")
+                .validator(|count| {
+                    count
+                        .parse::<usize>()
+                        .map(|_| ())
+                        .map_err(|_| format!("{} is not a number", count))
+                }),
+        )
+        .arg(
+            Arg::with_name("target_home")
+                .help("renders {{HOME}} and the XDG directories against this directory instead of your home")
+                .long("target-home")
+                .global(true)
+                .takes_value(true)
+                .value_name("DIR"),
+        )
         .subcommand(
             SubCommand::with_name("init")
                 .about("initialize Hermione manifest file")
                 .version(env!("CARGO_PKG_VERSION"))
-                .author(env!("CARGO_PKG_AUTHORS")),
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("FORMAT")
+                        .help("file format of the manifest")
+                        .long("format")
+                        .takes_value(true)
+                        .default_value("yaml")
+                        .possible_values(ManifestFormat::variants()),
+                ),
         )
         .subcommand(
             SubCommand::with_name("install")
@@ -42,8 +108,65 @@ fn main() -> Result<()> {
                 .arg(
                     Arg::with_name("SOURCE")
                         .help("pointer to package (git URL or local file path)")
+                        .required_unless("FROM_LOCK")
+                        .conflicts_with("FROM_LOCK")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("FROM_LOCK")
+                        .help("installs every package of a Hermfile written by 'herm export'")
+                        .long("from-lock")
+                        .takes_value(true)
+                        .value_name("HERMFILE"),
+                )
+                .arg(
+                    Arg::with_name("ON_CONFLICT")
+                        .help("what to do when a mapped file already exists, overriding the manifest")
+                        .long("on-conflict")
+                        .takes_value(true)
+                        .possible_values(ConflictStrategy::variants()),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("adopt")
+                .about("move existing files into a package and manage them with Hermione")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("PATHS")
+                        .help("files to adopt")
                         .required(true)
+                        .multiple(true)
                         .index(1),
+                )
+                .arg(
+                    Arg::with_name("INTO")
+                        .help("path to the package which adopts the files")
+                        .long("into")
+                        .takes_value(true)
+                        .value_name("PACKAGE_PATH")
+                        .default_value("."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("backups")
+                .about("inspect and restore files moved aside by installs")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("lists backed up files")
+                        .alias("ls"),
+                )
+                .subcommand(
+                    SubCommand::with_name("restore")
+                        .about("restores a backed up file to its original location")
+                        .arg(
+                            Arg::with_name("BACKUP_ID")
+                                .help("id of the backup, as shown by 'herm backups list'")
+                                .required(true)
+                                .index(1),
+                        ),
                 ),
         )
         .subcommand(
@@ -92,6 +215,31 @@ fn main() -> Result<()> {
                         .value_name("PACKAGE_PATH")
                         .default_value(".")
                         .index(1),
+                )
+                .arg(
+                    Arg::with_name("DETERMINISTIC")
+                        .help("pack a reproducible archive: sorted entries, normalized owner, mode and time (also on when SOURCE_DATE_EPOCH is set)")
+                        .long("deterministic"),
+                )
+                .arg(
+                    Arg::with_name("WRITE_INTEGRITY")
+                        .help("also write the computed integrity to the source manifest")
+                        .long("write-integrity"),
+                )
+                .arg(
+                    Arg::with_name("OUT_DIR")
+                        .help("directory to write the archive to, instead of the current directory")
+                        .long("out-dir")
+                        .takes_value(true)
+                        .value_name("DIR")
+                        .conflicts_with("OUT"),
+                )
+                .arg(
+                    Arg::with_name("OUT")
+                        .help("path to write the archive to")
+                        .long("out")
+                        .takes_value(true)
+                        .value_name("FILE"),
                 ),
         )
         .subcommand(
@@ -124,33 +272,389 @@ fn main() -> Result<()> {
                         .multiple(true)
                         .default_value("")
                         .index(1),
+                )
+                .arg(
+                    Arg::with_name("ON_CONFLICT")
+                        .help("what to do when a mapped file already exists, overriding the manifest")
+                        .long("on-conflict")
+                        .takes_value(true)
+                        .possible_values(ConflictStrategy::variants()),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("writes the installed packages as a Hermfile pinned to exact versions")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("PATH")
+                        .help("file to write, as TOML when it ends in .toml, printed to stdout when missing")
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("shows what Hermione installed, removed, upgraded and repaired")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("PACKAGE_ID")
+                        .help("only show operations on this package")
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("info")
+                .about("shows the details of an installed package, package file, package directory or repository package")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("PACKAGE")
+                        .help("installed package id, path to a package file or directory, source URL or repository package id")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("inspect")
+                .about("shows the entries, manifest, integrity and hooks of a package archive without installing it")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("PACKAGE_FILE")
+                        .help("path to a .hpkg file")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("lint")
+                .about("checks a package directory and reports every problem in its manifest")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("PACKAGE_PATH")
+                        .help("package directory")
+                        .default_value(".")
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("schema")
+                .about("prints the JSON Schema of hermione.yml for editor completion and validation")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS")),
+        )
+        .subcommand(
+            SubCommand::with_name("migrate-manifest")
+                .about("upgrades the manifest of a package directory to the current format in place")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("PACKAGE_PATH")
+                        .help("package directory")
+                        .default_value(".")
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("search")
+                .about("searches the configured repositories for packages")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("QUERY")
+                        .help("part of the package id to look for")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("outdated")
+                .about("lists installed packages with newer versions available, failing if there are any")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS")),
+        )
+        .subcommand(
+            SubCommand::with_name("pin")
+                .about("holds an installed package back from upgrades")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("PACKAGE_ID")
+                        .help("package reverse domain id <com.example.package>")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("VERSION_REQ")
+                        .help("semver requirement upgrades have to meet, defaults to the installed version")
+                        .index(2),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("unpin")
+                .about("lets a pinned package be upgraded again")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("PACKAGE_ID")
+                        .help("package reverse domain id <com.example.package>")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("rollback")
+                .about("switches a package back to a previously installed version without downloading it")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("PACKAGE_ID")
+                        .help("package reverse domain id <com.example.package>")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("VERSION")
+                        .help("version to go back to, defaults to the newest kept version before the current one")
+                        .long("to")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("undo")
+                .about("reverts the most recent operation")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS")),
+        )
+        .subcommand(
+            SubCommand::with_name("sync")
+                .about("installs, upgrades and optionally removes packages to match a Hermfile")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("HERMFILE")
+                        .help("path to the Hermfile listing the wanted packages")
+                        .default_value("Hermfile")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("prune")
+                        .help("removes installed packages which the Hermfile does not list")
+                        .long("prune"),
+                )
+                .arg(
+                    Arg::with_name("ON_CONFLICT")
+                        .help("what to do when a mapped file already exists, overriding the manifest")
+                        .long("on-conflict")
+                        .takes_value(true)
+                        .possible_values(ConflictStrategy::variants()),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("checks installed packages for drift")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("PACKAGE_NAMES")
+                        .help("package names, defaults to all installed packages")
+                        .multiple(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("repair")
+                .about("restores drifted or missing files of installed packages")
+                .version(env!("CARGO_PKG_VERSION"))
+                .author(env!("CARGO_PKG_AUTHORS"))
+                .arg(
+                    Arg::with_name("PACKAGE_NAMES")
+                        .help("package names, defaults to all installed packages")
+                        .multiple(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("run_hooks")
+                        .help("runs the pre_install and post_install hooks around the repair")
+                        .long("run-hooks"),
                 ),
         )
         .get_matches();
 
     let subcommand_name = String::from(matches.subcommand_name().unwrap_or("error"));
-    let package_service = PackageService::new()?;
+    let subcommand_matches = matches.subcommand().1;
+    let dry_run = matches.is_present("dry_run")
+        || subcommand_matches.is_some_and(|m| m.is_present("dry_run"));
+    let dry_run_format = matches
+        .value_of("dry_run")
+        .or_else(|| subcommand_matches.and_then(|m| m.value_of("dry_run")))
+        .unwrap_or("text");
+
+    let output_format = matches
+        .value_of("output")
+        .or_else(|| subcommand_matches.and_then(|m| m.value_of("output")))
+        .map(str::parse)
+        .transpose()?
+        .unwrap_or_default();
+    let output = Output::new(output_format);
+    let events: Arc<dyn EventSink> = if output.is_json() {
+        Arc::new(JsonSink::default())
+    } else {
+        Arc::new(PrettySink::new())
+    };
+
+    let mut package_service = PackageService::new()?;
+    if let Some(root) = matches
+        .value_of_os("root")
+        .or_else(|| subcommand_matches.and_then(|m| m.value_of_os("root")))
+    {
+        package_service = package_service.with_root(root)?;
+    }
+    if let Some(keep_versions) = matches
+        .value_of("keep_versions")
+        .or_else(|| subcommand_matches.and_then(|m| m.value_of("keep_versions")))
+    {
+        package_service.keep_versions = keep_versions.parse()?;
+    }
+    if let Some(target_home) = matches
+        .value_of_os("target_home")
+        .or_else(|| subcommand_matches.and_then(|m| m.value_of_os("target_home")))
+    {
+        package_service = package_service.with_target_home(target_home)?;
+    }
+    package_service.output = output.clone();
+    package_service.events = events.clone();
+    if dry_run {
+        if ["adopt", "init", "new", "package"].contains(&subcommand_name.as_str()) {
+            return Err(HermioneError::Policy(format!(
+                "'{}' does not support --dry-run",
+                subcommand_name
+            ))
+            .into());
+        }
+        package_service.plan = Some(Plan::new()?);
+    }
+    let plan = package_service.plan.clone();
 
-    let lockfile = package_service.lockfile()?;
+    // Dry runs leave the disk alone, so there is nothing to lock before anything was installed
+    let lockfile = if plan.is_none() || package_service.install_dir().is_dir() {
+        Some(package_service.lockfile()?)
+    } else {
+        None
+    };
 
-    if subcommand_name != "implode" {
+    if subcommand_name != "implode" && plan.is_none() {
         package_service.init()?;
     };
 
+    let result = run_subcommand(&matches, package_service);
+
+    if let (Some(plan), true) = (plan, result.is_ok()) {
+        if output.is_json() {
+            output.set("plan", plan.operations())?;
+        } else if dry_run_format == "json" {
+            println!("{}", plan.to_json()?);
+        } else {
+            events.emit(Event::info(
+                "Dry run, nothing was changed. Planned operations:",
+            ));
+            for (index, operation) in plan.operations().iter().enumerate() {
+                events.emit(Event::detail(format!("{}. {}", index + 1, operation)));
+            }
+        }
+    }
+
+    if output.is_json() {
+        output.set("command", &subcommand_name)?;
+        output.set("ok", result.is_ok())?;
+        if let Err(e) = &result {
+            output.set("error", format!("{:#}", e))?;
+            output.set(
+                "error_kind",
+                HermioneError::find(e).map(HermioneError::kind),
+            )?;
+            output.set("exit_code", error::exit_code(e))?;
+        }
+        println!("{}", output.to_json()?);
+    }
+
+    if let (Some(lockfile), true) = (lockfile, subcommand_name != "implode" || dry_run) {
+        lockfile
+            .release()
+            .map_err(|e| eyre!("Unable to release lockfile because: {}", e))?;
+    }
+    result
+}
+
+/// Runs the action for the given subcommand.
+///
+/// Returns an empty Result.
+fn run_subcommand(matches: &ArgMatches, package_service: PackageService) -> Result<()> {
     match matches.subcommand() {
-        ("init", Some(_init_matches)) => {
-            actions::init_action::InitAction {}.execute(package_service)?;
+        ("init", Some(init_matches)) => {
+            let format = init_matches
+                .value_of("FORMAT")
+                .expect("No format provided")
+                .parse()?;
+            actions::init_action::InitAction { format }.execute(package_service)?;
         }
         ("install", Some(install_matches)) => {
-            let package_source = install_matches
-                .value_of("SOURCE")
-                .expect("Unable to read source");
+            let on_conflict = install_matches
+                .value_of("ON_CONFLICT")
+                .map(str::parse)
+                .transpose()?;
+
+            match install_matches.value_of("FROM_LOCK") {
+                Some(hermfile_path) => actions::sync_action::SyncAction {
+                    hermfile_path: PathBuf::from(hermfile_path),
+                    prune: false,
+                    on_conflict,
+                }
+                .execute(package_service)?,
+                None => {
+                    let package_source = install_matches
+                        .value_of("SOURCE")
+                        .expect("Unable to read source");
 
-            actions::install_action::InstallAction {
-                package_source: String::from(package_source),
+                    actions::install_action::InstallAction {
+                        package_source: String::from(package_source),
+                        on_conflict,
+                    }
+                    .execute(package_service)?
+                }
+            }
+        }
+        ("adopt", Some(adopt_matches)) => {
+            let paths = adopt_matches
+                .values_of("PATHS")
+                .expect("Unable to read paths to adopt");
+            let package_path = adopt_matches
+                .value_of("INTO")
+                .expect("no package path provided");
+
+            actions::adopt_action::AdoptAction {
+                paths: paths.map(String::from).collect(),
+                package_path: String::from(package_path),
             }
             .execute(package_service)?;
         }
+        ("backups", Some(backups_matches)) => match backups_matches.subcommand() {
+            ("restore", Some(restore_matches)) => {
+                let backup_id = restore_matches
+                    .value_of("BACKUP_ID")
+                    .expect("Unable to read backup id");
+
+                actions::backup_restore_action::BackupRestoreAction {
+                    backup_id: String::from(backup_id),
+                }
+                .execute(package_service)?;
+            }
+            _ => {
+                actions::backup_list_action::BackupListAction {}.execute(package_service)?;
+            }
+        },
         ("implode", Some(implode_matches)) => {
             let confirmed = implode_matches.is_present("confirm");
             actions::implode_action::ImplodeAction {
@@ -190,6 +694,10 @@ fn main() -> Result<()> {
 
             actions::package_action::PackageAction {
                 package_path: String::from(package_path),
+                deterministic: package_matches.is_present("DETERMINISTIC"),
+                write_integrity: package_matches.is_present("WRITE_INTEGRITY"),
+                out_dir: package_matches.value_of("OUT_DIR").map(PathBuf::from),
+                out: package_matches.value_of("OUT").map(PathBuf::from),
             }
             .execute(package_service)?;
         }
@@ -198,27 +706,166 @@ fn main() -> Result<()> {
                 .values_of("PACKAGE_NAMES")
                 .expect("Unable to read package names");
 
+            let on_conflict = upgrade_matches
+                .value_of("ON_CONFLICT")
+                .map(str::parse)
+                .transpose()?;
+
             actions::upgrade_action::UpgradeAction {
                 package_names: package_names
                     .map(String::from)
                     .filter(|s| !s.is_empty())
                     .collect(),
+                on_conflict,
             }
             .execute(package_service)?;
         }
-        (subcommand, _) => {
-            let mut logger = Logger::new();
-            logger.error(format!("Unknown subcommand '{}'", subcommand));
-            return Err(eyre!("Unknown subcommand. Try 'help'"));
+        ("export", Some(export_matches)) => {
+            actions::export_action::ExportAction {
+                path: export_matches.value_of("PATH").map(PathBuf::from),
+            }
+            .execute(package_service)?;
         }
-    };
+        ("history", Some(history_matches)) => {
+            actions::history_action::HistoryAction {
+                package_id: history_matches.value_of("PACKAGE_ID").map(String::from),
+            }
+            .execute(package_service)?;
+        }
+        ("info", Some(info_matches)) => {
+            actions::info_action::InfoAction {
+                target: String::from(
+                    info_matches
+                        .value_of("PACKAGE")
+                        .expect("Unable to read package"),
+                ),
+            }
+            .execute(package_service)?;
+        }
+        ("inspect", Some(inspect_matches)) => {
+            actions::inspect_action::InspectAction {
+                package_path: PathBuf::from(
+                    inspect_matches
+                        .value_of("PACKAGE_FILE")
+                        .expect("Unable to read package file"),
+                ),
+            }
+            .execute(package_service)?;
+        }
+        ("lint", Some(lint_matches)) => {
+            actions::lint_action::LintAction {
+                package_path: PathBuf::from(
+                    lint_matches
+                        .value_of("PACKAGE_PATH")
+                        .expect("Unable to read package path"),
+                ),
+            }
+            .execute(package_service)?;
+        }
+        ("schema", _schema_matches) => {
+            actions::schema_action::SchemaAction {}.execute(package_service)?;
+        }
+        ("migrate-manifest", Some(migrate_matches)) => {
+            actions::migrate_manifest_action::MigrateManifestAction {
+                package_path: PathBuf::from(
+                    migrate_matches
+                        .value_of("PACKAGE_PATH")
+                        .expect("Unable to read package path"),
+                ),
+            }
+            .execute(package_service)?;
+        }
+        ("search", Some(search_matches)) => {
+            actions::search_action::SearchAction {
+                query: String::from(
+                    search_matches
+                        .value_of("QUERY")
+                        .expect("Unable to read query"),
+                ),
+            }
+            .execute(package_service)?;
+        }
+        ("outdated", _outdated_matches) => {
+            actions::outdated_action::OutdatedAction {}.execute(package_service)?;
+        }
+        ("pin", Some(pin_matches)) => {
+            actions::pin_action::PinAction {
+                package_id: String::from(
+                    pin_matches
+                        .value_of("PACKAGE_ID")
+                        .expect("Unable to read package id"),
+                ),
+                version_req: pin_matches.value_of("VERSION_REQ").map(String::from),
+            }
+            .execute(package_service)?;
+        }
+        ("unpin", Some(unpin_matches)) => {
+            actions::unpin_action::UnpinAction {
+                package_id: String::from(
+                    unpin_matches
+                        .value_of("PACKAGE_ID")
+                        .expect("Unable to read package id"),
+                ),
+            }
+            .execute(package_service)?;
+        }
+        ("rollback", Some(rollback_matches)) => {
+            actions::rollback_action::RollbackAction {
+                package_id: String::from(
+                    rollback_matches
+                        .value_of("PACKAGE_ID")
+                        .expect("Unable to read package id"),
+                ),
+                version: rollback_matches.value_of("VERSION").map(String::from),
+            }
+            .execute(package_service)?;
+        }
+        ("undo", _undo_matches) => {
+            actions::undo_action::UndoAction {}.execute(package_service)?;
+        }
+        ("sync", Some(sync_matches)) => {
+            let hermfile_path = sync_matches
+                .value_of("HERMFILE")
+                .expect("Unable to read Hermfile path");
 
-    if subcommand_name == "implode" {
-        Ok(())
-    } else {
-        match lockfile.release() {
-            Ok(_) => Ok(()),
-            Err(e) => Err(eyre!("Unable to release lockfile because: {}", e)),
+            let on_conflict = sync_matches
+                .value_of("ON_CONFLICT")
+                .map(str::parse)
+                .transpose()?;
+
+            actions::sync_action::SyncAction {
+                hermfile_path: PathBuf::from(hermfile_path),
+                prune: sync_matches.is_present("prune"),
+                on_conflict,
+            }
+            .execute(package_service)?;
+        }
+        ("verify", Some(verify_matches)) => {
+            let package_names = verify_matches
+                .values_of("PACKAGE_NAMES")
+                .map(|values| values.map(String::from).collect())
+                .unwrap_or_default();
+
+            actions::verify_action::VerifyAction { package_names }.execute(package_service)?;
+        }
+        ("repair", Some(repair_matches)) => {
+            let package_names = repair_matches
+                .values_of("PACKAGE_NAMES")
+                .map(|values| values.map(String::from).collect())
+                .unwrap_or_default();
+
+            actions::repair_action::RepairAction {
+                package_names,
+                run_hooks: repair_matches.is_present("run_hooks"),
+            }
+            .execute(package_service)?;
+        }
+        (subcommand, _) => {
+            package_service
+                .events
+                .emit(Event::error(format!("Unknown subcommand '{}'", subcommand)));
+            return Err(eyre!("Unknown subcommand. Try 'help'"));
         }
     }
+    Ok(())
 }
@@ -1,19 +1,122 @@
-use color_eyre::eyre::{eyre, Result, WrapErr};
+use color_eyre::eyre::{eyre, Report, Result};
+use schemars::JsonSchema;
 use semver::Version;
 use serde::{Deserialize, Serialize};
+use ssri::Integrity;
 
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+use crate::error::HermioneError;
 use crate::file_mapping_definition::FileMappingDefinition;
 use crate::hooks::Hooks;
 
 const MANIFEST_FILE_NAME: &str = "hermione.yml";
 
+/// Every file name a manifest is recognized by.
+const MANIFEST_FILE_NAMES: &[&str] = &[
+    "hermione.yml",
+    "hermione.yaml",
+    "hermione.toml",
+    "hermione.json",
+];
+
+/// Newest manifest format this release of Hermione understands.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// File formats a manifest can be written in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ManifestFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ManifestFormat {
+    /// Names accepted on the command line.
+    pub fn variants() -> &'static [&'static str] {
+        &["yaml", "toml", "json"]
+    }
+
+    /// Returns the file name new manifests in this format are written to.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ManifestFormat::Yaml => MANIFEST_FILE_NAME,
+            ManifestFormat::Toml => "hermione.toml",
+            ManifestFormat::Json => "hermione.json",
+        }
+    }
+
+    /// Returns the format of a manifest file by its extension, YAML for anything unrecognized.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => ManifestFormat::Toml,
+            Some("json") => ManifestFormat::Json,
+            _ => ManifestFormat::Yaml,
+        }
+    }
+
+    /// Parses a manifest written in this format without checking it.
+    pub fn parse(self, contents: &str) -> Result<Manifest> {
+        let parsed = match self {
+            ManifestFormat::Yaml => serde_yaml::from_str(contents).map_err(|e| e.to_string()),
+            ManifestFormat::Toml => toml::from_str(contents).map_err(|e| e.to_string()),
+            ManifestFormat::Json => serde_json::from_str(contents).map_err(|e| e.to_string()),
+        };
+        parsed.map_err(|e| {
+            HermioneError::Manifest(format!("Could not parse manifest {}: {}", self, e)).into()
+        })
+    }
+
+    /// Writes a manifest in this format.
+    pub fn serialize(self, manifest: &Manifest) -> Result<String> {
+        Ok(match self {
+            ManifestFormat::Yaml => serde_yaml::to_string(manifest)?,
+            ManifestFormat::Toml => toml::to_string(manifest)?,
+            ManifestFormat::Json => serde_json::to_string_pretty(manifest)? + "\n",
+        })
+    }
+}
+
+impl fmt::Display for ManifestFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ManifestFormat::Yaml => "yaml",
+            ManifestFormat::Toml => "toml",
+            ManifestFormat::Json => "json",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for ManifestFormat {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "yaml" | "yml" => Ok(ManifestFormat::Yaml),
+            "toml" => Ok(ManifestFormat::Toml),
+            "json" => Ok(ManifestFormat::Json),
+            _ => Err(eyre!(
+                "Unknown manifest format {}, expected one of {}",
+                s,
+                Self::variants().join(", ")
+            )),
+        }
+    }
+}
+
 /// Manifest represents the definition of your Hermione package.
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Manifest {
+    /// Format of this manifest, missing in manifests written before formats were versioned.
+    /// Hermione refuses manifests in a format newer than it understands.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest_version: Option<u32>,
     /// Name of your package 📦.
     pub name: String,
     /// Your name 😀.
@@ -24,25 +127,92 @@ pub struct Manifest {
     pub id: String,
     /// Manifest version
     pub version: String,
+    /// Oldest release of Hermione able to install this package, as semver.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_herm_version: Option<String>,
+    /// Globs of extra files to archive along with the mapping inputs, like hook helper scripts,
+    /// READMEs, licenses or template partials. `*` stays within a directory, `**` crosses them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Files left out of the archive even when included, in `.gitignore` syntax.
+    /// Lines of a `.hermignore` file next to the manifest are added to these.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
     /// Mappings define the core operation of Hermione.
     /// Here is where you define the `what` and the `where`
     /// . The what being the file you want to move and the where being where do you want to move it.
     pub mappings: Vec<FileMappingDefinition>,
+    /// Subresource integrity of every included file by path, recorded by `herm package`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub files: BTreeMap<String, String>,
     /// Optional hooks for duckscript    
     pub hooks: Option<Hooks>,
 }
 
 impl Manifest {
-    /// Generates a Manifest struct from a given `hermione.yml` path.
+    /// Generates a Manifest struct from a given manifest path, in the format its extension says.
     pub fn new_from_path(path: PathBuf) -> Result<Manifest> {
         if path.is_file() {
-            let yaml = fs::read_to_string(path)?;
-            let manifest: Manifest =
-                serde_yaml::from_str(&yaml).wrap_err("Could not parse manifest yaml")?;
-            Version::parse(&manifest.version).wrap_err("Invalid semver version in manifest")?;
+            let contents = fs::read_to_string(&path)?;
+            let manifest = ManifestFormat::from_path(&path).parse(&contents)?;
+            manifest.check()?;
             Ok(manifest)
         } else {
-            Err(eyre!("Looks like {} is not a file", path.display()))
+            Err(
+                HermioneError::Manifest(format!("Looks like {} is not a file", path.display()))
+                    .into(),
+            )
+        }
+    }
+
+    /// Generates a Manifest struct from the manifest in a package directory.
+    pub fn new_from_dir(package_path: &Path) -> Result<Manifest> {
+        Self::new_from_path(Self::path_in(package_path)?)
+    }
+
+    /// Returns the path of the manifest in a package directory.
+    ///
+    /// Errors if there is no manifest or more than one, since Hermione could not tell which one counts.
+    pub fn path_in(package_path: &Path) -> Result<PathBuf> {
+        let found = MANIFEST_FILE_NAMES
+            .iter()
+            .map(|file_name| package_path.join(file_name))
+            .filter(|path| path.is_file())
+            .collect::<Vec<_>>();
+        match found.as_slice() {
+            [path] => Ok(path.clone()),
+            [] => Err(HermioneError::Manifest(format!(
+                "No manifest ({}) found in {}",
+                MANIFEST_FILE_NAMES.join(", "),
+                package_path.display()
+            ))
+            .into()),
+            _ => Err(HermioneError::Manifest(format!(
+                "Found more than one manifest in {}: {}. Keep only one of them",
+                package_path.display(),
+                found
+                    .iter()
+                    .filter_map(|path| path.file_name())
+                    .map(|file_name| file_name.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+            .into()),
+        }
+    }
+
+    /// Writes the manifest to a path, in the format its extension says.
+    pub fn store(&self, path: &Path) -> Result<()> {
+        fs::write(path, ManifestFormat::from_path(path).serialize(self)?)?;
+        Ok(())
+    }
+
+    /// Returns true if the contents match the integrity recorded in `files` for the given path.
+    /// Paths without recorded integrity never match.
+    pub fn verify_file_contents(&self, path: &str, contents: &[u8]) -> Result<bool> {
+        match self.files.get(path) {
+            Some(checksum) => Ok(checksum.parse::<Integrity>()?.check(contents).is_ok()),
+            None => Ok(false),
         }
     }
 
@@ -58,12 +228,75 @@ impl Manifest {
     where
         R: io::Read,
     {
-        let manifest: Manifest = serde_yaml::from_reader(data)?;
-        Version::parse(&manifest.version).wrap_err("Invalid semver version in manifest")?;
+        Self::new_from_reader_with_format(data, ManifestFormat::Yaml)
+    }
+
+    /// Generates a Manifest struct from reader data in the given format.
+    pub fn new_from_reader_with_format<R>(mut data: R, format: ManifestFormat) -> Result<Manifest>
+    where
+        R: io::Read,
+    {
+        let mut contents = String::new();
+        data.read_to_string(&mut contents)?;
+        let manifest = format.parse(&contents)?;
+        manifest.check()?;
         Ok(manifest)
     }
 
+    /// Errors if the manifest version is not valid semver or the manifest
+    /// needs a newer release of Hermione than the one running.
+    fn check(&self) -> Result<()> {
+        Version::parse(&self.version).map_err(|e| {
+            HermioneError::Manifest(format!("Invalid semver version in manifest: {}", e))
+        })?;
+        Self::check_format(self.manifest_version.unwrap_or(0))?;
+        if let Some(min_herm_version) = &self.min_herm_version {
+            let required = Version::parse(min_herm_version).map_err(|e| {
+                HermioneError::Manifest(format!(
+                    "Invalid semver min_herm_version in manifest: {}",
+                    e
+                ))
+            })?;
+            let running = Version::parse(env!("CARGO_PKG_VERSION"))?;
+            if required > running {
+                return Err(HermioneError::Manifest(format!(
+                    "{} requires Hermione {} or newer but this is {}, please upgrade herm",
+                    self.id, required, running
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Errors if a manifest format is newer than this release of Hermione understands.
+    pub fn check_format(manifest_version: u32) -> Result<()> {
+        if manifest_version > MANIFEST_VERSION {
+            Err(HermioneError::Manifest(format!(
+                "Manifest format {} is newer than format {} which this herm understands, please upgrade herm",
+                manifest_version, MANIFEST_VERSION
+            ))
+            .into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the JSON Schema of the manifest for editors to complete and validate `hermione.yml` with.
+    /// It is generated from the serde types so it always matches what Hermione accepts.
+    pub fn json_schema() -> Result<String> {
+        Ok(serde_json::to_string_pretty(&schemars::schema_for!(
+            Manifest
+        ))?)
+    }
+
+    /// Returns the default manifest file name, `hermione.yml`.
     pub fn manifest_file_name() -> String {
         String::from(MANIFEST_FILE_NAME)
     }
+
+    /// Returns every file name a manifest is recognized by.
+    pub fn manifest_file_names() -> &'static [&'static str] {
+        MANIFEST_FILE_NAMES
+    }
 }
@@ -0,0 +1,182 @@
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::file_mapping::FileMapping;
+use crate::file_mapping_definition::FileMappingDefinition;
+
+/// State of an installed file mapping compared to what was installed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingState {
+    /// Output links to the package file and the package file matches its integrity.
+    Ok,
+    /// Output no longer exists.
+    Missing,
+    /// Package file in the install directory no longer matches its integrity.
+    Modified,
+    /// Output has been replaced by a file which does not match the package file.
+    Replaced,
+    /// Output is a link to something other than the package file.
+    Foreign,
+    /// Output already existed at install and was left alone because the conflict strategy was `skip`.
+    Skipped,
+}
+
+impl MappingState {
+    /// Returns true for every state other than `Ok` and `Skipped`.
+    pub fn is_drift(self) -> bool {
+        self != MappingState::Ok && self != MappingState::Skipped
+    }
+}
+
+impl fmt::Display for MappingState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MappingState::Ok => "ok",
+            MappingState::Missing => "missing",
+            MappingState::Modified => "modified",
+            MappingState::Replaced => "replaced",
+            MappingState::Foreign => "foreign",
+            MappingState::Skipped => "skipped",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Result of comparing one installed mapping against the system.
+pub struct MappingCheck {
+    pub definition: FileMappingDefinition,
+    pub mapping: FileMapping,
+    pub state: MappingState,
+}
+
+impl MappingCheck {
+    /// Checks a rendered mapping of an installed package.
+    ///
+    /// ### Arguments
+    ///
+    /// * definition - Mapping definition from the installed manifest.
+    /// * mapping - The definition rendered against the install directory.
+    /// * skipped - Whether the output was skipped at install, see `Receipt::skipped`.
+    pub fn new(
+        definition: FileMappingDefinition,
+        mapping: FileMapping,
+        skipped: bool,
+    ) -> Result<Self> {
+        let state = Self::state_of(&definition, &mapping, skipped)?;
+        Ok(Self {
+            definition,
+            mapping,
+            state,
+        })
+    }
+
+    fn state_of(
+        definition: &FileMappingDefinition,
+        mapping: &FileMapping,
+        skipped: bool,
+    ) -> Result<MappingState> {
+        let package_file_intact = mapping.input().is_file()
+            && (definition.integrity.is_none()
+                || definition.verify_integrity_of(mapping.input())?);
+        if !package_file_intact {
+            return Ok(MappingState::Modified);
+        }
+
+        let output: &Path = mapping.output();
+        match fs::symlink_metadata(output) {
+            Err(_) => Ok(MappingState::Missing),
+            Ok(_) if skipped && !mapping.is_linked() => Ok(MappingState::Skipped),
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                if mapping.is_linked() {
+                    Ok(MappingState::Ok)
+                } else {
+                    Ok(MappingState::Foreign)
+                }
+            }
+            Ok(metadata) if metadata.is_file() && definition.integrity.is_some() => {
+                if definition.verify_integrity_of(output)? {
+                    Ok(MappingState::Ok)
+                } else {
+                    Ok(MappingState::Replaced)
+                }
+            }
+            Ok(_) => Ok(MappingState::Replaced),
+        }
+    }
+
+    /// Returns String print out of the check.
+    pub fn display_line(&self) -> String {
+        let state = match self.state {
+            MappingState::Ok => format!("<green>{}</>", self.state),
+            MappingState::Skipped => format!("<yellow>{}</>", self.state),
+            _ => format!("<red>{}</>", self.state),
+        };
+        format!(
+            "{} {} <blue>-></> {}",
+            state,
+            self.mapping.input().display(),
+            self.mapping.output().display()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ssri::Integrity;
+    use tempfile::TempDir;
+
+    #[cfg(target_family = "unix")]
+    use std::os::unix::fs::symlink;
+
+    fn fixture(package_dir: &Path, home_dir: &Path) -> (FileMappingDefinition, FileMapping) {
+        fs::write(package_dir.join("a.txt"), "bamboo").expect("Unable to write file in test");
+        let definition = FileMappingDefinition::new(
+            String::from("a.txt"),
+            String::from("panda.txt"),
+            None,
+            Some(Integrity::from("bamboo").to_string()),
+            None,
+        );
+        let mapping = FileMapping::new(package_dir.join("a.txt"), home_dir.join("panda.txt"));
+        (definition, mapping)
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_states() {
+        let package_dir = TempDir::new().expect("Unable to create dir in test");
+        let home_dir = TempDir::new().expect("Unable to create dir in test");
+        let (definition, mapping) = fixture(package_dir.path(), home_dir.path());
+        let check = |definition: &FileMappingDefinition, mapping: &FileMapping| {
+            MappingCheck::state_of(definition, mapping, false).expect("Unable to check in test")
+        };
+
+        assert_eq!(MappingState::Missing, check(&definition, &mapping));
+
+        symlink(mapping.input(), mapping.output()).expect("Unable to link in test");
+        assert_eq!(MappingState::Ok, check(&definition, &mapping));
+
+        fs::remove_file(mapping.output()).expect("Unable to unlink in test");
+        symlink(home_dir.path(), mapping.output()).expect("Unable to link in test");
+        assert_eq!(MappingState::Foreign, check(&definition, &mapping));
+
+        fs::remove_file(mapping.output()).expect("Unable to unlink in test");
+        fs::write(mapping.output(), "not bamboo").expect("Unable to write file in test");
+        assert_eq!(MappingState::Replaced, check(&definition, &mapping));
+
+        assert_eq!(
+            MappingState::Skipped,
+            MappingCheck::state_of(&definition, &mapping, true).expect("Unable to check in test")
+        );
+
+        fs::write(mapping.input(), "eucalyptus").expect("Unable to write file in test");
+        assert_eq!(MappingState::Modified, check(&definition, &mapping));
+    }
+}
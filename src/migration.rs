@@ -0,0 +1,135 @@
+use color_eyre::eyre::Result;
+use serde::Serialize;
+
+use crate::manifest::{Manifest, ManifestFormat, MANIFEST_VERSION};
+
+/// A step upgrading the text of a manifest by one format.
+type Step = fn(&str, ManifestFormat) -> String;
+
+/// Steps in order, the first one upgrading manifests written before formats were versioned.
+/// Steps work on the text so comments and formatting in the manifest survive.
+const STEPS: &[Step] = &[add_manifest_version];
+
+/// Result of migrating a manifest to the current format.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Migration {
+    /// Format the manifest was in, 0 when it had none.
+    pub from: u32,
+    /// Format the manifest is in now.
+    pub to: u32,
+    /// Text of the migrated manifest.
+    #[serde(skip)]
+    pub contents: String,
+}
+
+impl Migration {
+    /// Returns true when the manifest was already in the current format.
+    pub fn is_current(&self) -> bool {
+        self.from == self.to
+    }
+}
+
+/// Upgrades the text of a manifest to the current format.
+///
+/// Errors if the manifest can not be parsed or is in a format newer than this release understands.
+///
+/// ### Arguments
+///
+/// * contents - Text of the manifest.
+/// * format - File format the manifest is written in.
+///
+/// Returns a Migration as a Result.
+pub fn migrate(contents: &str, format: ManifestFormat) -> Result<Migration> {
+    let manifest = format.parse(contents)?;
+    let from = manifest.manifest_version.unwrap_or(0);
+    Manifest::check_format(from)?;
+
+    let migrated = STEPS[from as usize..]
+        .iter()
+        .fold(String::from(contents), |contents, step| {
+            step(&contents, format)
+        });
+    Manifest::new_from_reader_with_format(migrated.as_bytes(), format)?;
+    Ok(Migration {
+        from,
+        to: MANIFEST_VERSION,
+        contents: migrated,
+    })
+}
+
+/// Format 1 adds `manifest_version`, inserted above the first key.
+fn add_manifest_version(contents: &str, format: ManifestFormat) -> String {
+    if format == ManifestFormat::Json {
+        return contents.replacen('{', "{\n  \"manifest_version\": 1,", 1);
+    }
+    let key = match format {
+        ManifestFormat::Toml => "manifest_version = 1\n",
+        _ => "manifest_version: 1\n",
+    };
+    let mut migrated = String::with_capacity(contents.len() + key.len());
+    let mut inserted = false;
+    for line in contents.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if !inserted && !trimmed.is_empty() && !trimmed.starts_with('#') && trimmed != "---" {
+            migrated.push_str(key);
+            inserted = true;
+        }
+        migrated.push_str(line);
+    }
+    migrated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGACY: &str = "---
+# Dotfiles for my laptop
+name: Dots
+authors: []
+description: My dotfiles
+id: org.example.dots
+version: 0.1.0
+mappings: []
+";
+
+    #[test]
+    fn test_migrates_legacy_manifest_keeping_comments() {
+        let migration = migrate(LEGACY, ManifestFormat::Yaml).expect("Unable to migrate in test");
+
+        assert_eq!(0, migration.from);
+        assert_eq!(MANIFEST_VERSION, migration.to);
+        assert!(migration
+            .contents
+            .starts_with("---\n# Dotfiles for my laptop\nmanifest_version: 1\nname: Dots\n"));
+        assert!(migrate(&migration.contents, ManifestFormat::Yaml)
+            .expect("Unable to migrate in test")
+            .is_current());
+    }
+
+    #[test]
+    fn test_migrates_toml_and_json() {
+        let toml = "name = \"Dots\"\nauthors = []\ndescription = \"My dotfiles\"\nid = \"org.example.dots\"\nversion = \"0.1.0\"\nmappings = []\n";
+        let migration = migrate(toml, ManifestFormat::Toml).expect("Unable to migrate in test");
+        assert!(migration.contents.starts_with("manifest_version = 1\nname"));
+
+        let json = "{\"name\": \"Dots\", \"authors\": [], \"description\": \"My dotfiles\", \"id\": \"org.example.dots\", \"version\": \"0.1.0\", \"mappings\": []}";
+        let migration = migrate(json, ManifestFormat::Json).expect("Unable to migrate in test");
+        assert_eq!(0, migration.from);
+        assert!(migrate(&migration.contents, ManifestFormat::Json)
+            .expect("Unable to migrate in test")
+            .is_current());
+    }
+
+    #[test]
+    fn test_rejects_newer_formats() {
+        let newer = LEGACY.replace("name: Dots", "manifest_version: 99\nname: Dots");
+
+        assert!(migrate(&newer, ManifestFormat::Yaml).is_err());
+        assert!(Manifest::new_from_reader(newer.as_bytes()).is_err());
+
+        let too_new_for_herm =
+            LEGACY.replace("mappings: []", "min_herm_version: 999.0.0\nmappings: []");
+        assert!(Manifest::new_from_reader(too_new_for_herm.as_bytes()).is_err());
+    }
+}
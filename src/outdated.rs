@@ -0,0 +1,201 @@
+use color_eyre::eyre::Result;
+use semver::{Version, VersionReq};
+use serde::Serialize;
+
+use std::fmt;
+
+use crate::config::HermioneConfig;
+use crate::events::{self, Event};
+use crate::installed_package::InstalledPackage;
+use crate::manifest::Manifest;
+use crate::package_service::PackageService;
+use crate::pins::Pins;
+use crate::plan::Plan;
+use crate::receipt::Receipt;
+use crate::repositories::repository_contents::RepositoryContents;
+
+/// How an installed package compares to the versions available for it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OutdatedPackage {
+    pub id: String,
+    /// Installed version.
+    pub current: String,
+    /// Newest available version meeting the pin, or semver compatible with the installed version.
+    pub latest_compatible: Option<String>,
+    /// Newest available version.
+    pub latest: Option<String>,
+    /// Requirement the package is pinned to, if it is pinned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pin: Option<String>,
+    /// Whether an upgrade is available. Pinned packages only count versions meeting their pin.
+    pub outdated: bool,
+}
+
+impl fmt::Display for OutdatedPackage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unknown = String::from("?");
+        let color = if self.outdated { "yellow" } else { "green" };
+        write!(
+            f,
+            "{} <{}>{}</> compatible {} latest {}",
+            self.id,
+            color,
+            self.current,
+            self.latest_compatible.as_ref().unwrap_or(&unknown),
+            self.latest.as_ref().unwrap_or(&unknown)
+        )?;
+        if let Some(pin) = &self.pin {
+            write!(f, " <yellow>pinned {}</>", pin)?;
+        }
+        Ok(())
+    }
+}
+
+impl OutdatedPackage {
+    /// Compares an installed version against the available ones.
+    ///
+    /// ### Arguments
+    ///
+    /// * id - Id of the package.
+    /// * current - Installed version.
+    /// * pin - Requirement the package is pinned to, if any.
+    /// * available - Versions available from the package source and repositories.
+    ///
+    /// Returns an OutdatedPackage as a Result.
+    pub fn new(
+        id: &str,
+        current: &str,
+        pin: Option<String>,
+        available: &[Version],
+    ) -> Result<Self> {
+        let current_version = Version::parse(current)?;
+        let compatible = match &pin {
+            Some(pin) => VersionReq::parse(pin)?,
+            None => VersionReq::parse(&format!("^{}", current))?,
+        };
+        let latest = available.iter().max();
+        let latest_compatible = available
+            .iter()
+            .filter(|version| compatible.matches(version))
+            .max();
+        let newest = if pin.is_some() {
+            latest_compatible
+        } else {
+            latest
+        };
+        Ok(Self {
+            id: String::from(id),
+            current: String::from(current),
+            latest_compatible: latest_compatible.map(Version::to_string),
+            latest: latest.map(Version::to_string),
+            pin,
+            outdated: newest.is_some_and(|newest| newest > &current_version),
+        })
+    }
+}
+
+/// Compares every installed package against the newest versions offered by the source
+/// it was installed from and by the configured repositories.
+/// Sources which can not be reached are skipped with a warning.
+///
+/// Returns the installed packages ordered by id as a Result.
+pub fn check(package_service: &PackageService) -> Result<Vec<OutdatedPackage>> {
+    let events = package_service.events.clone();
+    let pins = Pins::load(package_service)?;
+    let repositories = HermioneConfig::load(&package_service.config_dir())?
+        .available_repositories(events.as_ref())?;
+    // Sources are quietly downloaded into a throwaway staging directory to read their manifests
+    let probe = PackageService {
+        plan: Some(Plan::new()?),
+        events: events::silent(),
+        ..package_service.clone()
+    };
+
+    let mut installed = package_service.list_installed_packages()?;
+    installed.sort_by(|a, b| a.manifest.id.cmp(&b.manifest.id));
+    installed
+        .iter()
+        .map(|installed_package| {
+            let mut available = repository_versions(&repositories, &installed_package.manifest.id);
+            match source_version(&probe, installed_package) {
+                Ok(Some(version)) => available.push(version),
+                Ok(None) => {}
+                Err(e) => events.emit(Event::warning(format!(
+                    "Unable to check the source of {}: {}",
+                    installed_package.manifest.id, e
+                ))),
+            }
+            OutdatedPackage::new(
+                &installed_package.manifest.id,
+                &installed_package.manifest.version,
+                pins.get(&installed_package.manifest.id).cloned(),
+                &available,
+            )
+        })
+        .collect()
+}
+
+/// Returns the version currently offered by the source a package was installed from,
+/// or None when no source was recorded.
+fn source_version(
+    probe: &PackageService,
+    installed_package: &InstalledPackage,
+) -> Result<Option<Version>> {
+    let receipt = Receipt::load(&installed_package.local_path);
+    let source = match receipt {
+        Ok(receipt) => receipt.source,
+        Err(_) => return Ok(None),
+    };
+    let downloaded = probe.clone().download(source)?;
+    let manifest = Manifest::new_from_dir(&downloaded.local_path)?;
+    Ok(Some(Version::parse(&manifest.version)?))
+}
+
+/// Returns the versions of a package listed by the repositories, ignoring any which are not semver.
+fn repository_versions(repositories: &[RepositoryContents], package_id: &str) -> Vec<Version> {
+    repositories
+        .iter()
+        .flat_map(|repository| repository.available_packages.iter())
+        .filter(|available_package| available_package.id == package_id)
+        .flat_map(|available_package| available_package.available_versions.iter())
+        .filter_map(|available_version| Version::parse(&available_version.version).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(versions: &[&str]) -> Vec<Version> {
+        versions
+            .iter()
+            .map(|version| Version::parse(version).expect("Unable to parse version in test"))
+            .collect()
+    }
+
+    #[test]
+    fn test_compares_compatible_and_latest() {
+        let available = versions(&["1.2.0", "1.4.1", "2.0.0"]);
+
+        let unpinned = OutdatedPackage::new("org.example.dots", "1.2.0", None, &available)
+            .expect("Unable to compare versions in test");
+        assert_eq!(Some(String::from("1.4.1")), unpinned.latest_compatible);
+        assert_eq!(Some(String::from("2.0.0")), unpinned.latest);
+        assert!(unpinned.outdated);
+
+        let pinned = OutdatedPackage::new(
+            "org.example.dots",
+            "1.2.0",
+            Some(String::from("=1.2.0")),
+            &available,
+        )
+        .expect("Unable to compare versions in test");
+        assert_eq!(Some(String::from("1.2.0")), pinned.latest_compatible);
+        assert!(!pinned.outdated);
+
+        let unknown = OutdatedPackage::new("org.example.dots", "1.2.0", None, &[])
+            .expect("Unable to compare versions in test");
+        assert_eq!(None, unknown.latest);
+        assert!(!unknown.outdated);
+    }
+}
@@ -0,0 +1,174 @@
+use color_eyre::eyre::{eyre, Report, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use crate::installed_package::InstalledPackage;
+use crate::mapping_check::{MappingCheck, MappingState};
+
+/// Format of what a command prints to stdout.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum OutputFormat {
+    /// Colored log lines meant for people.
+    #[default]
+    Human,
+    /// A single JSON document, progress moves to stderr as JSON lines.
+    Json,
+}
+
+impl OutputFormat {
+    /// Returns the names accepted on the command line.
+    pub fn variants() -> &'static [&'static str] {
+        &["human", "json"]
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "human" => Ok(OutputFormat::Human),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(eyre!(
+                "Unknown output format '{}', expected one of: {}",
+                s,
+                Self::variants().join(", ")
+            )),
+        }
+    }
+}
+
+/// Output collects the fields of the JSON document a command prints when `--output json` is given.
+/// Clones share the same document so actions can fill it in while `main` prints it.
+#[derive(Clone, Debug, Default)]
+pub struct Output {
+    pub format: OutputFormat,
+    document: Arc<Mutex<Map<String, Value>>>,
+}
+
+impl Output {
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            document: Arc::new(Mutex::new(Map::new())),
+        }
+    }
+
+    /// Returns true when a JSON document is printed instead of human readable output.
+    pub fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// Sets a top level field of the document, replacing any previous value.
+    ///
+    /// ### Arguments
+    ///
+    /// * key - Field name, these are part of the stable output and must not be renamed.
+    /// * value - Anything serializable.
+    ///
+    /// Returns an empty Result.
+    pub fn set<T: Serialize>(&self, key: &str, value: T) -> Result<()> {
+        let value = serde_json::to_value(value)?;
+        self.document
+            .lock()
+            .expect("Unable to write output document")
+            .insert(String::from(key), value);
+        Ok(())
+    }
+
+    /// Returns the document as pretty printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        let document = self
+            .document
+            .lock()
+            .expect("Unable to read output document")
+            .clone();
+        Ok(serde_json::to_string_pretty(&Value::Object(document))?)
+    }
+}
+
+/// JSON representation of an installed package.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PackageSummary {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub path: PathBuf,
+    /// Requirement the package is pinned to, if it is pinned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pin: Option<String>,
+}
+
+impl From<&InstalledPackage> for PackageSummary {
+    fn from(installed_package: &InstalledPackage) -> Self {
+        Self {
+            id: installed_package.manifest.id.clone(),
+            name: installed_package.manifest.name.clone(),
+            version: installed_package.manifest.version.clone(),
+            description: installed_package.manifest.description.clone(),
+            path: installed_package.local_path.clone(),
+            pin: None,
+        }
+    }
+}
+
+impl PackageSummary {
+    /// Consumes the summary and records what the package is pinned to.
+    pub fn with_pin(self, pin: Option<String>) -> Self {
+        Self { pin, ..self }
+    }
+}
+
+/// JSON representation of a verified mapping.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MappingSummary {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub state: MappingState,
+}
+
+impl From<&MappingCheck> for MappingSummary {
+    fn from(check: &MappingCheck) -> Self {
+        Self {
+            input: check.mapping.input().to_path_buf(),
+            output: check.mapping.output().to_path_buf(),
+            state: check.state,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_is_shared_between_clones() {
+        let output = Output::new(OutputFormat::Json);
+        output
+            .clone()
+            .set("ok", true)
+            .expect("Unable to set field in test");
+        assert_eq!(
+            "{\n  \"ok\": true\n}",
+            output
+                .to_json()
+                .expect("Unable to serialize output in test")
+        );
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(
+            OutputFormat::Json,
+            "json"
+                .parse::<OutputFormat>()
+                .expect("Unable to parse format")
+        );
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+}
@@ -0,0 +1,275 @@
+use color_eyre::eyre::Result;
+use semver::Version;
+use serde::Serialize;
+use url::Url;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::HermioneConfig;
+use crate::conflict_strategy::ConflictStrategy;
+use crate::error::HermioneError;
+use crate::events;
+use crate::file_mapping_definition::FileMappingDefinition;
+use crate::installed_package::InstalledPackage;
+use crate::manifest::Manifest;
+use crate::mapping_check::MappingState;
+use crate::package_service::PackageService;
+use crate::packer::Packer;
+use crate::pins::Pins;
+use crate::plan::Plan;
+use crate::receipt::Receipt;
+use crate::versions::Versions;
+
+/// Where the information about a package was read from.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InfoOrigin {
+    /// The package is installed.
+    Installed,
+    /// A local package archive.
+    Archive,
+    /// A local package directory.
+    Directory,
+    /// A package source URL, downloaded to a throwaway directory.
+    Source,
+    /// The newest version a configured repository lists for the id.
+    Repository,
+}
+
+/// A mapping of a package rendered against the directory it is, or would be, installed in.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MappingInfo {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    /// Platform family the mapping is limited to, if any.
+    pub platform: Option<String>,
+    /// What happens when the output already exists.
+    pub on_conflict: ConflictStrategy,
+    /// State of the installed mapping, None when the package is not installed
+    /// or the mapping is for another platform.
+    pub state: Option<MappingState>,
+}
+
+impl MappingInfo {
+    /// Returns String print out of the mapping.
+    pub fn display_line(&self) -> String {
+        let state = match self.state {
+            Some(MappingState::Ok) => String::from("<green>ok</> "),
+            Some(MappingState::Skipped) => String::from("<yellow>skipped</> "),
+            Some(state) => format!("<red>{}</> ", state),
+            None => String::new(),
+        };
+        let platform = match &self.platform {
+            Some(platform) => format!(" ({} only)", platform),
+            None => String::new(),
+        };
+        format!(
+            "{}{} <blue>-></> {}{}",
+            state,
+            self.input.display(),
+            self.output.display(),
+            platform
+        )
+    }
+}
+
+/// Everything worth knowing about a package, installed or not.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PackageInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub authors: Vec<String>,
+    pub version: String,
+    pub origin: InfoOrigin,
+    /// Source the package is installed from or was read from.
+    pub source: Option<String>,
+    /// RFC 3339 timestamp of when the package was installed.
+    pub installed_at: Option<String>,
+    /// Directory of the installed package.
+    pub path: Option<PathBuf>,
+    /// Requirement the package is pinned to, if it is pinned.
+    pub pin: Option<String>,
+    /// Names of the hooks the manifest defines.
+    pub hooks: Vec<String>,
+    pub mappings: Vec<MappingInfo>,
+}
+
+impl PackageInfo {
+    /// Looks up a package by what the user typed: a source URL, a path to a package archive
+    /// or directory, the id of an installed package or the id of a package in a configured repository.
+    ///
+    /// Returns a PackageInfo as a Result.
+    pub fn resolve(package_service: &PackageService, target: &str) -> Result<Self> {
+        if Url::parse(target).is_ok() {
+            return Self::from_source(package_service, target, InfoOrigin::Source);
+        }
+
+        let path = Path::new(target);
+        if path.is_file() {
+            let manifest = Packer::new(path.to_path_buf()).get_manifest_from_archive()?;
+            return Self::from_manifest(
+                package_service,
+                manifest,
+                InfoOrigin::Archive,
+                Some(Self::file_source(path)?),
+            );
+        }
+        if path.is_dir() {
+            let manifest = Manifest::new_from_dir(path)?;
+            return Self::from_manifest(
+                package_service,
+                manifest,
+                InfoOrigin::Directory,
+                Some(Self::file_source(path)?),
+            );
+        }
+
+        if let Ok(installed_package) = package_service
+            .clone()
+            .get_installed_package(String::from(target))
+        {
+            return Self::from_installed(&installed_package);
+        }
+
+        let repositories = HermioneConfig::load(&package_service.config_dir())?
+            .available_repositories(package_service.events.as_ref())?;
+        let newest = repositories
+            .iter()
+            .flat_map(|repository| repository.available_packages.iter())
+            .filter(|available_package| available_package.id == target)
+            .flat_map(|available_package| available_package.available_versions.iter())
+            .filter_map(|available_version| {
+                Version::parse(&available_version.version)
+                    .ok()
+                    .map(|version| (version, available_version.url.clone()))
+            })
+            .max();
+        match newest {
+            Some((_, url)) => Self::from_source(package_service, &url, InfoOrigin::Repository),
+            None => Err(HermioneError::NotInstalled(format!(
+                "{} is not installed, not a package file or directory and not in any configured repository",
+                target
+            ))
+            .into()),
+        }
+    }
+
+    /// Returns the information of an installed package, including the state of its mappings.
+    pub fn from_installed(installed_package: &InstalledPackage) -> Result<Self> {
+        let package_service = &installed_package.package_service;
+        let receipt = Receipt::load(&installed_package.local_path).ok();
+        let checks = installed_package.verify()?;
+        let mappings = installed_package
+            .manifest
+            .mappings
+            .iter()
+            .map(|definition| {
+                let info =
+                    Self::mapping_info(package_service, definition, &installed_package.local_path)?;
+                let state = checks
+                    .iter()
+                    .filter(|_| definition.valid_platform_family())
+                    .find(|check| check.mapping.output() == info.output)
+                    .map(|check| check.state);
+                Ok(MappingInfo { state, ..info })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let manifest = &installed_package.manifest;
+        Ok(Self {
+            source: receipt.as_ref().map(|receipt| receipt.source.clone()),
+            installed_at: receipt.and_then(|receipt| receipt.installed_at),
+            path: Some(installed_package.local_path.clone()),
+            pin: Pins::load(package_service)?.get(&manifest.id).cloned(),
+            mappings,
+            ..Self::from_manifest(
+                package_service,
+                manifest.clone(),
+                InfoOrigin::Installed,
+                None,
+            )?
+        })
+    }
+
+    /// Returns the information of a package which is not installed, with its mappings
+    /// rendered against the directory it would be installed in.
+    pub fn from_manifest(
+        package_service: &PackageService,
+        manifest: Manifest,
+        origin: InfoOrigin,
+        source: Option<String>,
+    ) -> Result<Self> {
+        let install_path = Versions::new(package_service, &manifest.id).path(&manifest.version);
+        let mappings = manifest
+            .mappings
+            .iter()
+            .map(|definition| Self::mapping_info(package_service, definition, &install_path))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            hooks: manifest
+                .hooks
+                .as_ref()
+                .map(|hooks| hooks.defined())
+                .unwrap_or_default(),
+            id: manifest.id,
+            name: manifest.name,
+            description: manifest.description,
+            authors: manifest.authors,
+            version: manifest.version,
+            origin,
+            source,
+            installed_at: None,
+            path: None,
+            pin: None,
+            mappings,
+        })
+    }
+
+    /// Downloads a source into a throwaway staging directory to read its manifest.
+    fn from_source(
+        package_service: &PackageService,
+        source: &str,
+        origin: InfoOrigin,
+    ) -> Result<Self> {
+        let probe = PackageService {
+            plan: Some(Plan::new()?),
+            events: events::silent(),
+            ..package_service.clone()
+        };
+        let downloaded = probe.download(String::from(source))?;
+        let manifest = Manifest::new_from_dir(&downloaded.local_path)?;
+        let source = Receipt::load(&downloaded.local_path)
+            .map(|receipt| receipt.source)
+            .unwrap_or_else(|_| String::from(source));
+        Self::from_manifest(package_service, manifest, origin, Some(source))
+    }
+
+    fn mapping_info(
+        package_service: &PackageService,
+        definition: &FileMappingDefinition,
+        package_path: &Path,
+    ) -> Result<MappingInfo> {
+        let mapping = definition
+            .clone()
+            .render_file_mapping(package_service, package_path.to_path_buf())?;
+        Ok(MappingInfo {
+            input: mapping.input().to_path_buf(),
+            output: mapping.output().to_path_buf(),
+            platform: definition.platform.clone(),
+            on_conflict: package_service
+                .on_conflict
+                .or(definition.on_conflict)
+                .unwrap_or_default(),
+            state: None,
+        })
+    }
+
+    /// Returns an absolute `file://` URL for a local package path.
+    fn file_source(path: &Path) -> Result<String> {
+        let path = fs::canonicalize(path)?;
+        Ok(Url::from_file_path(&path)
+            .map(|url| url.to_string())
+            .unwrap_or_else(|_| path.display().to_string()))
+    }
+}
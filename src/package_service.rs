@@ -2,23 +2,43 @@ use color_eyre::eyre::{eyre, Result, WrapErr};
 use directories::{BaseDirs, ProjectDirs};
 use fs_extra::dir;
 use lockfile::Lockfile;
-use paris::Logger;
+use ssri::Integrity;
 use url::Url;
 
+use std::env;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::Arc;
 
+use crate::backup::Backup;
+use crate::conflict_strategy::ConflictStrategy;
 use crate::downloaded_package::DownloadedPackage;
 use crate::downloader::Downloader;
+use crate::error::HermioneError;
+use crate::events::{self, Event, EventSink};
+use crate::history::{History, HistoryEntry, HistoryOperation};
 use crate::installed_package::InstalledPackage;
 use crate::manifest::Manifest;
+use crate::output::Output;
 use crate::packer::Packer;
+use crate::plan::{Plan, PlannedOperation};
+use crate::receipt::Receipt;
+use crate::versions::Versions;
 
 const QUALIFIER: &str = "dev";
 const ORGANIZATION: &str = "hermione";
 
+/// Environment variable relocating everything Hermione stores, see `PackageService::with_root`.
+pub const HERMIONE_HOME: &str = "HERMIONE_HOME";
+
+/// Environment variable setting how many previous versions of each package are kept.
+pub const HERMIONE_KEEP_VERSIONS: &str = "HERMIONE_KEEP_VERSIONS";
+
+/// Number of previous versions of each package kept for rollback unless configured otherwise.
+pub const DEFAULT_KEEP_VERSIONS: usize = 2;
+
 #[cfg(test)]
 const APPLICATION: &str = "herm_test";
 
@@ -26,36 +46,113 @@ const APPLICATION: &str = "herm_test";
 const APPLICATION: &str = "herm";
 
 /// PackageService provides some of the core logic around initializing `hermione`.
-/// It is responsible for wrapping a logger for subsequent actions to use,
+/// It is responsible for carrying the event sink for subsequent actions to report to,
 /// providing information for OS specific file directories and provide
 /// entry points for initiating the install and remove actions.
 #[derive(Clone, Debug)]
 pub struct PackageService {
     pub project_dirs: ProjectDirs,
+    /// Directory holding the install, download and config directories instead of the OS defaults.
+    pub root: Option<PathBuf>,
+    /// Directory `{{HOME}}` and the XDG facts render against instead of the current user's home.
+    pub target_home: Option<PathBuf>,
+    /// Number of previous versions of each package kept for rollback.
+    pub keep_versions: usize,
+    /// Conflict strategy chosen for this invocation, overriding per-mapping defaults.
+    pub on_conflict: Option<ConflictStrategy>,
+    /// When present, mutating operations are recorded here instead of being performed.
+    pub plan: Option<Plan>,
+    /// Document printed to stdout when JSON output is requested.
+    pub output: Output,
+    /// Receives progress events, silent unless the caller picks a sink.
+    pub events: Arc<dyn EventSink>,
 }
 
 impl PackageService {
     /// Create a new PackageService.
     ///
-    /// ### Arguments
-    ///
-    /// * logger - Instance of Logger.
+    /// The directories are relocated under `HERMIONE_HOME` when it is set
+    /// and `HERMIONE_KEEP_VERSIONS` overrides how many previous versions are kept.
     ///
     /// Returns an instance of PackageService as a Result.
     pub fn new() -> Result<Self> {
-        Ok(PackageService {
+        let keep_versions = match env::var(HERMIONE_KEEP_VERSIONS) {
+            Ok(keep_versions) if !keep_versions.is_empty() => {
+                keep_versions.parse().wrap_err_with(|| {
+                    format!(
+                        "{} must be a number, not {}",
+                        HERMIONE_KEEP_VERSIONS, keep_versions
+                    )
+                })?
+            }
+            _ => DEFAULT_KEEP_VERSIONS,
+        };
+        let package_service = PackageService {
             project_dirs: Self::project_dirs()?,
+            root: None,
+            target_home: None,
+            keep_versions,
+            on_conflict: None,
+            plan: None,
+            output: Output::default(),
+            events: events::silent(),
+        };
+        match env::var_os(HERMIONE_HOME) {
+            Some(root) if !root.is_empty() => package_service.with_root(root),
+            _ => Ok(package_service),
+        }
+    }
+
+    /// Relocates the install, download and config directories to `data`, `cache`
+    /// and `config` below the given root.
+    ///
+    /// ### Arguments
+    ///
+    /// * root - Directory to keep everything in, relative paths are resolved against the working directory.
+    ///
+    /// Returns the PackageService as a Result.
+    pub fn with_root<P: Into<PathBuf>>(self, root: P) -> Result<Self> {
+        Ok(PackageService {
+            root: Some(Self::absolute(root.into())?),
+            ..self
+        })
+    }
+
+    /// Renders `{{HOME}}` and the XDG facts against the given directory instead of the
+    /// current user's home, e.g. to populate a chroot or container layer.
+    ///
+    /// ### Arguments
+    ///
+    /// * target_home - Directory to treat as home, relative paths are resolved against the working directory.
+    ///
+    /// Returns the PackageService as a Result.
+    pub fn with_target_home<P: Into<PathBuf>>(self, target_home: P) -> Result<Self> {
+        Ok(PackageService {
+            target_home: Some(Self::absolute(target_home.into())?),
+            ..self
         })
     }
 
+    fn absolute(path: PathBuf) -> Result<PathBuf> {
+        if path.is_absolute() {
+            Ok(path)
+        } else {
+            Ok(env::current_dir()
+                .wrap_err("Unable to determine working directory")?
+                .join(path))
+        }
+    }
+
     /// Creates the download and install dir for the respective OS.
     ///
     /// Returns a bool as a Result.
     pub fn init(&self) -> Result<bool> {
-        let mut logger = Logger::new();
         let d_dir = self.download_dir();
         if !d_dir.is_dir() {
-            logger.info(format!("Creating download directory {}", &d_dir.display()));
+            self.events.emit(Event::info(format!(
+                "Creating download directory {}",
+                &d_dir.display()
+            )));
             fs::create_dir_all(&d_dir).wrap_err_with(|| {
                 format!("Unable to create download directory {}", d_dir.display())
             })?;
@@ -63,7 +160,10 @@ impl PackageService {
 
         let i_dir = self.install_dir();
         if !i_dir.is_dir() {
-            logger.info(format!("Creating install directory: {}", &i_dir.display(),));
+            self.events.emit(Event::info(format!(
+                "Creating install directory: {}",
+                &i_dir.display(),
+            )));
             fs::create_dir_all(&i_dir).wrap_err_with(|| {
                 format!("Unable to create install directory {}", i_dir.display())
             })?;
@@ -74,12 +174,77 @@ impl PackageService {
 
     /// Returns a PathBuf to the download directory for the respective OS.
     pub fn download_dir(&self) -> PathBuf {
-        self.project_dirs.cache_dir().to_path_buf()
+        match &self.root {
+            Some(root) => root.join("cache"),
+            None => self.project_dirs.cache_dir().to_path_buf(),
+        }
+    }
+
+    /// Returns a PathBuf to the directory packages are downloaded into.
+    /// This is the download directory unless dry running, in which case it is a throwaway staging directory.
+    pub fn download_target_dir(&self) -> PathBuf {
+        match &self.plan {
+            Some(plan) => plan.staging_dir(),
+            None => self.download_dir(),
+        }
+    }
+
+    /// Performs a mutating operation, or records it in the plan when dry running.
+    ///
+    /// ### Arguments
+    ///
+    /// * operation - Description of what the action does.
+    /// * action - Closure doing the actual work.
+    ///
+    /// Returns an empty Result.
+    pub fn perform<F>(&self, operation: PlannedOperation, action: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        match &self.plan {
+            Some(plan) => {
+                plan.record(operation);
+                Ok(())
+            }
+            None => action(),
+        }
+    }
+
+    /// Restores a backed up file to its original location.
+    ///
+    /// Returns an empty Result.
+    pub fn restore_backup(&self, backup: Backup) -> Result<()> {
+        self.perform(
+            PlannedOperation::Restore {
+                backup_id: backup.id.clone(),
+                path: backup.original_path.clone(),
+            },
+            || {
+                self.events.emit(Event::detail(backup.restore()?));
+                Ok(())
+            },
+        )
     }
 
     /// Returns a PathBuf to the install directory for the respective OS.
     pub fn install_dir(&self) -> PathBuf {
-        self.project_dirs.data_dir().to_path_buf()
+        match &self.root {
+            Some(root) => root.join("data"),
+            None => self.project_dirs.data_dir().to_path_buf(),
+        }
+    }
+
+    /// Returns a PathBuf to the configuration directory for the respective OS.
+    pub fn config_dir(&self) -> PathBuf {
+        match &self.root {
+            Some(root) => root.join("config"),
+            None => self.project_dirs.config_dir().to_path_buf(),
+        }
+    }
+
+    /// Returns a PathBuf to the directory holding files displaced by installs.
+    pub fn backup_dir(&self) -> PathBuf {
+        self.install_dir().join(".backups")
     }
 
     /// Returns a lockfile path
@@ -99,22 +264,63 @@ impl PackageService {
 
                 Ok(lockfile)
             }
-            Err(err) => Err(eyre!(
+            Err(err) => Err(HermioneError::Locked(format!(
                 "Is Hermione already running? Unable to obtain lockfile at {} because: {}",
                 lockfile_path.display(),
                 err
-            )),
+            ))
+            .into()),
         }
     }
 
-    /// Returns a PathBuf to the users home directory for their respective OS.
+    /// Returns a PathBuf to the users home directory for their respective OS,
+    /// or the target home when one was chosen.
     pub fn home_dir(&self) -> Result<PathBuf> {
+        if let Some(target_home) = &self.target_home {
+            return Ok(target_home.clone());
+        }
         match BaseDirs::new() {
             Some(base_dirs) => Ok(base_dirs.home_dir().to_path_buf()),
             None => Err(eyre!("Unable to find HOME directory")),
         }
     }
 
+    /// Returns the directories which can be used as template variables in mapping outputs,
+    /// e.g. `{{HOME}}` or `{{XDG_CONFIG_HOME}}`.
+    ///
+    /// With a target home the XDG facts are the XDG defaults below it,
+    /// since the environment describes the current user rather than the target.
+    pub fn facts(&self) -> Result<Vec<(String, PathBuf)>> {
+        if let Some(target_home) = &self.target_home {
+            return Ok(vec![
+                (String::from("HOME"), target_home.clone()),
+                (String::from("XDG_CONFIG_HOME"), target_home.join(".config")),
+                (
+                    String::from("XDG_DATA_HOME"),
+                    target_home.join(".local").join("share"),
+                ),
+                (String::from("XDG_CACHE_HOME"), target_home.join(".cache")),
+            ]);
+        }
+        let base_dirs = BaseDirs::new().ok_or_else(|| eyre!("Unable to find HOME directory"))?;
+        let facts = vec![
+            (String::from("HOME"), self.home_dir()?),
+            (
+                String::from("XDG_CONFIG_HOME"),
+                base_dirs.config_dir().to_path_buf(),
+            ),
+            (
+                String::from("XDG_DATA_HOME"),
+                base_dirs.data_dir().to_path_buf(),
+            ),
+            (
+                String::from("XDG_CACHE_HOME"),
+                base_dirs.cache_dir().to_path_buf(),
+            ),
+        ];
+        Ok(facts)
+    }
+
     /// Gets an instance of an installed package if one exists.
     ///
     /// ### Arguments
@@ -124,8 +330,7 @@ impl PackageService {
     /// Returns an InstalledPackage as a Result.
     pub fn get_installed_package(self, package_id: String) -> Result<InstalledPackage> {
         let package_path = self.installed_package_path(&package_id)?;
-        let manifest_path = package_path.join(Manifest::manifest_file_name());
-        let manifest = Manifest::new_from_path(manifest_path)?;
+        let manifest = Manifest::new_from_dir(&package_path)?;
         Ok(InstalledPackage {
             local_path: package_path,
             manifest,
@@ -133,7 +338,7 @@ impl PackageService {
         })
     }
 
-    /// Gets a PathBuf of an installed package if one exists.
+    /// Gets a PathBuf to the current version of an installed package if one exists.
     ///
     /// ### Arguments
     ///
@@ -141,13 +346,17 @@ impl PackageService {
     ///
     /// Returns an PathBuf as a Result.
     pub fn installed_package_path(&self, package_name: &str) -> Result<PathBuf> {
-        let path = self.install_dir().join(package_name);
-        if path.is_dir() && !package_name.trim().is_empty() {
+        let current_path = Versions::new(self, package_name).current_path();
+        if let (Some(path), false) = (current_path, package_name.trim().is_empty()) {
             Ok(path)
         } else if package_name.trim().is_empty() {
             Err(eyre!("Package name can not be empty."))
         } else {
-            Err(eyre!("It appears that {} isn't installed.", package_name))
+            Err(HermioneError::NotInstalled(format!(
+                "It appears that {} isn't installed.",
+                package_name
+            ))
+            .into())
         }
     }
 
@@ -163,12 +372,10 @@ impl PackageService {
             entries.sort();
             let dirs = entries.iter().filter(|entry_path| entry_path.is_dir());
             let installed = dirs
-                .map(|entry| {
+                .filter_map(|entry| {
                     let package_service = self.clone();
-                    let local_path = entry.clone();
-                    let manifest_path = local_path.join(Manifest::manifest_file_name());
-
-                    match Manifest::new_from_path(manifest_path) {
+                    let local_path = Versions::from_root(self, entry.clone()).current_path()?;
+                    match Manifest::new_from_dir(&local_path) {
                         Ok(manifest) => Some(InstalledPackage {
                             local_path,
                             manifest,
@@ -177,8 +384,6 @@ impl PackageService {
                         Err(_) => None,
                     }
                 })
-                .filter(|opt| opt.is_some())
-                .map(|opt| opt.expect("Unable to read manifest from installed package"))
                 .collect();
             Ok(installed)
         }
@@ -204,8 +409,28 @@ impl PackageService {
     ///
     /// Returns an InstalledPackage as a Result.
     pub fn download_and_install(self, src: String) -> Result<InstalledPackage> {
-        let downloaded_package = self.download(src)?;
-        Ok(downloaded_package.install()?)
+        let history = History::new(&self);
+        // Until the manifest is downloaded the source is all that identifies the package
+        let entry = HistoryEntry {
+            to_source: Some(src.clone()),
+            ..HistoryEntry::new(HistoryOperation::Install, &src)
+        };
+        let downloaded_package = match self.clone().download(src) {
+            Ok(downloaded_package) => downloaded_package,
+            Err(e) => return history.track(entry, |_| Err(e)),
+        };
+        let entry = match Manifest::new_from_dir(&downloaded_package.local_path) {
+            Ok(manifest) => HistoryEntry {
+                package: manifest.id,
+                ..entry
+            },
+            Err(_) => entry,
+        };
+        history.track(entry, |entry| {
+            let installed_package = downloaded_package.install()?;
+            let entry = entry.after(&installed_package);
+            Ok((installed_package, entry))
+        })
     }
 
     /// Initiate a download action for a given Hermione package location.
@@ -216,22 +441,26 @@ impl PackageService {
     ///
     /// Returns an DownloadedPackage as a Result.
     pub fn download(self, src: String) -> Result<DownloadedPackage> {
-        let download_dir = self.download_dir();
-        let mut logger = paris::Logger::new();
+        let download_dir = self.download_target_dir();
+        let events = self.events.clone();
         if !download_dir.exists() {
-            logger.info(format!(
+            events.emit(Event::info(format!(
                 "Creating download directory at {}",
                 &download_dir.display()
-            ));
+            )));
             dir::create_all(&download_dir, false)?;
         }
 
         let source_url = Url::parse(&src)
             .wrap_err_with(|| format!("Unable to parse package source url {}", &src))?;
 
-        if source_url.scheme().starts_with("http") {
-            logger.info("Downloading remote package");
-            Downloader::new(src, self).download()
+        let (downloaded_package, recorded_source, integrity) = if source_url
+            .scheme()
+            .starts_with("http")
+        {
+            events.emit(Event::info("Downloading remote package"));
+            let (downloaded_package, integrity) = Downloader::new(src.clone(), self).download()?;
+            (downloaded_package, src, Some(integrity))
         } else if source_url.scheme().starts_with("file") {
             let file_path = PathBuf::from(source_url.path());
             // Check if domain is available for case where file://./relative_file
@@ -246,17 +475,23 @@ impl PackageService {
             };
 
             if path.is_dir() {
-                logger.info(format!("Installing from directory {}", path.display()));
+                events.emit(Event::info(format!(
+                    "Installing from directory {}",
+                    path.display()
+                )));
 
-                let manifest_path = path.join(Manifest::manifest_file_name());
-                let manifest = Manifest::new_from_path(manifest_path)?;
+                let manifest = Manifest::new_from_dir(&path)?;
                 let download_package_dir = download_dir.join(manifest.id);
-                logger.info(format!(
+                events.emit(Event::info(format!(
                     "Copying Package {} -> {}",
                     path.display(),
                     download_package_dir.display(),
-                ));
+                )));
 
+                // Start from a clean copy so stale files never survive a re-download
+                if download_package_dir.exists() {
+                    fs::remove_dir_all(&download_package_dir)?;
+                }
                 let mut options = dir::CopyOptions::new();
                 options.copy_inside = true;
                 options.overwrite = true;
@@ -267,25 +502,48 @@ impl PackageService {
                     )
                 })?;
 
-                Ok(DownloadedPackage {
+                let downloaded_package = DownloadedPackage {
                     local_path: download_package_dir,
                     package_service: self,
-                })
+                };
+                (downloaded_package, Self::file_source(&path, src), None)
             } else if path.is_file() {
-                logger.info("Unpacking local file");
-                let local_path = Packer::new(path).unpack(self.download_dir())?;
-                Ok(DownloadedPackage {
+                events.emit(Event::info("Unpacking local file"));
+                let integrity = Integrity::from(&fs::read(&path)?).to_string();
+                let local_path = Packer::new(path.clone())
+                    .with_events(events.clone())
+                    .unpack(download_dir)?;
+                let downloaded_package = DownloadedPackage {
                     local_path,
                     package_service: self,
-                })
+                };
+                (
+                    downloaded_package,
+                    Self::file_source(&path, src),
+                    Some(integrity),
+                )
             } else {
-                Err(eyre!("Path to package does not exist: {}", path.display()))
+                return Err(eyre!("Path to package does not exist: {}", path.display()));
             }
         } else {
-            Err(eyre!(
+            return Err(eyre!(
                 "Package source URL has unrecognized scheme: {}",
                 source_url.scheme()
-            ))
+            ));
+        };
+
+        Receipt::new(recorded_source)
+            .with_integrity(integrity)
+            .store(&downloaded_package.local_path)?;
+        Ok(downloaded_package)
+    }
+
+    /// Returns an absolute `file://` URL for a local package path,
+    /// falling back to the source as given.
+    fn file_source(path: &Path, src: String) -> String {
+        match Url::from_file_path(path) {
+            Ok(url) => url.to_string(),
+            Err(_) => src,
         }
     }
 
@@ -298,16 +556,16 @@ impl PackageService {
     ///
     /// Returns an empty Result.
     pub fn purge_installed_packages(&self) -> Result<()> {
-        let mut logger = Logger::new();
-        logger.info("Started removing all installed packages");
+        self.events
+            .emit(Event::info("Started removing all installed packages"));
         let errored_uninstalled = self
             .list_installed_packages()?
             .into_iter()
             .map(|installed_package| {
-                logger.info(format!(
+                self.events.emit(Event::info(format!(
                     "Removing package: {} @ {}",
                     installed_package.manifest.id, installed_package.manifest.version
-                ));
+                )));
                 installed_package.remove().unwrap_or(false)
             })
             .filter(|was_removed| !was_removed)
@@ -324,31 +582,51 @@ impl PackageService {
     ///
     /// Returns an empty Result.
     pub fn implode(&self) -> Result<()> {
-        let mut logger = paris::Logger::new();
-        match self.purge_installed_packages() {
+        let purge_result = self
+            .purge_installed_packages()
+            .and_then(|_| Backup::list(&self.backup_dir()));
+        match purge_result {
+            Ok(remaining_backups) if !remaining_backups.is_empty() => {
+                self.events.emit(Event::warning(format!(
+                    "Leaving install directory {} in place because it still holds {} backup(s). See 'herm backups list'.",
+                    self.install_dir().display(),
+                    remaining_backups.len(),
+                )));
+            }
             Ok(_) => {
-                logger.info("All packages have been uninstalled.");
-                logger.info(format!(
+                self.events
+                    .emit(Event::info("All packages have been uninstalled."));
+                self.events.emit(Event::info(format!(
                     "Removing install directory: {}",
                     self.install_dir().display(),
-                ));
+                )));
                 if self.install_dir().exists() {
-                    fs::remove_dir_all(self.install_dir())?
+                    self.perform(
+                        PlannedOperation::Remove {
+                            path: self.install_dir(),
+                        },
+                        || Ok(fs::remove_dir_all(self.install_dir())?),
+                    )?;
                 }
             }
             Err(e) => {
-                logger.error(format!(
+                self.events.emit(Event::error(format!(
                     "Error deleting installed packages and installed directory because {}",
-                    e.to_string(),
-                ));
+                    e,
+                )));
             }
         }
-        logger.info(format!(
+        self.events.emit(Event::info(format!(
             "Removing download directory: {}",
             self.download_dir().display()
-        ));
+        )));
         if self.download_dir().exists() {
-            fs::remove_dir_all(self.download_dir())?;
+            self.perform(
+                PlannedOperation::Remove {
+                    path: self.download_dir(),
+                },
+                || Ok(fs::remove_dir_all(self.download_dir())?),
+            )?;
         }
         Ok(())
     }
@@ -456,10 +734,47 @@ mod tests {
             .installed_package_path(package_name)
             .expect("Package is not installed in test");
 
-        let expected = test_package_service.install_dir().join(&package_name);
+        let expected = test_package_service
+            .install_dir()
+            .join(package_name)
+            .join("0.1.0");
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_root_relocates_dirs() {
+        let package_service = PackageService::new()
+            .and_then(|package_service| package_service.with_root("/opt/herm"))
+            .expect("Unable to instantiate PackageService in test");
+        assert_eq!(
+            PathBuf::from("/opt/herm/data"),
+            package_service.install_dir()
+        );
+        assert_eq!(
+            PathBuf::from("/opt/herm/cache"),
+            package_service.download_dir()
+        );
+        assert_eq!(
+            PathBuf::from("/opt/herm/config"),
+            package_service.config_dir()
+        );
+    }
+
+    #[test]
+    fn test_target_home_facts() {
+        let package_service = PackageService::new()
+            .and_then(|package_service| package_service.with_target_home("/srv/rootfs/home/ada"))
+            .expect("Unable to instantiate PackageService in test");
+        let facts = package_service
+            .facts()
+            .expect("Unable to get facts in test");
+        assert!(facts.contains(&(String::from("HOME"), PathBuf::from("/srv/rootfs/home/ada"))));
+        assert!(facts.contains(&(
+            String::from("XDG_CONFIG_HOME"),
+            PathBuf::from("/srv/rootfs/home/ada/.config")
+        )));
+    }
+
     #[quickcheck]
     fn from_package_name_with_bogus_package_always_fails(name: String) -> bool {
         let package_service: PackageService =
@@ -1,111 +1,373 @@
-use color_eyre::eyre::{eyre, Result};
+use color_eyre::eyre::{eyre, Result, WrapErr};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use paris::Logger;
-use tar::{Archive, Builder};
+use globset::{GlobBuilder, GlobSetBuilder};
+use ignore::gitignore::GitignoreBuilder;
+use ignore::WalkBuilder;
+use ssri::Integrity;
+use tar::{Archive, Builder, EntryType, Header};
 
-use std::ffi::OsStr;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
 
-use crate::manifest::Manifest;
+use crate::error::HermioneError;
+use crate::events::{self, Event, EventSink};
+use crate::inspection::{ArchiveEntry, EntryKind, Inspection, IntegrityState, MappingIntegrity};
+use crate::manifest::{Manifest, ManifestFormat};
+
+/// Environment variable fixing the timestamp of every entry in deterministic archives,
+/// see https://reproducible-builds.org/specs/source-date-epoch/.
+pub const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+/// File next to the manifest listing files to leave out of the archive, in `.gitignore` syntax.
+pub const HERMIGNORE: &str = ".hermignore";
 
 pub struct Packer {
     pub package_path_buf: PathBuf,
+    /// Whether archives are packed deterministically, see `with_deterministic`.
+    pub deterministic: bool,
+    /// Whether computed integrity is written back to the source manifest.
+    pub write_integrity: bool,
+    /// Directory the archive is written to, the current directory when None.
+    pub out_dir: Option<PathBuf>,
+    /// Path the archive is written to, overriding `out_dir` and the default file name.
+    pub out: Option<PathBuf>,
+    events: Arc<dyn EventSink>,
 }
 
 impl Packer {
     pub fn new(package_path_buf: PathBuf) -> Self {
-        Self { package_path_buf }
+        Self {
+            package_path_buf,
+            deterministic: false,
+            write_integrity: false,
+            out_dir: None,
+            out: None,
+            events: events::silent(),
+        }
     }
 
-    /// Given package path, verify manifest file exists, return manifest PathBuf if present
-    fn get_manifest_path_buf(&self, package_path: &PathBuf) -> Result<PathBuf> {
-        let manifest_path = package_path.join(Manifest::manifest_file_name());
-        if manifest_path.is_file() {
-            Ok(manifest_path)
-        } else {
-            Err(eyre!(
-                "Manifest file does not exist in directory {}",
-                package_path.display()
-            ))
+    /// Consumes the Packer and sets the sink it reports progress to.
+    pub fn with_events(self, events: Arc<dyn EventSink>) -> Self {
+        Self { events, ..self }
+    }
+
+    /// Consumes the Packer and sets whether archives are packed deterministically:
+    /// entries sorted by name, owned by root, dated `SOURCE_DATE_EPOCH` (or the Unix epoch)
+    /// and with mode `0644`, or `0755` for executables.
+    /// Packing the same tree twice then yields the same bytes.
+    /// Setting `SOURCE_DATE_EPOCH` turns this on as well.
+    pub fn with_deterministic(self, deterministic: bool) -> Self {
+        Self {
+            deterministic,
+            ..self
+        }
+    }
+
+    /// Consumes the Packer and sets whether the integrity computed while packing is also
+    /// written to the source manifest. Otherwise it only ends up in the archived manifest.
+    pub fn with_write_integrity(self, write_integrity: bool) -> Self {
+        Self {
+            write_integrity,
+            ..self
         }
     }
 
-    fn get_manifest_from_archive(&self) -> Result<Manifest> {
+    /// Consumes the Packer and sets the directory the archive is written to as `<id>_<version>.hpkg`.
+    pub fn with_out_dir(self, out_dir: Option<PathBuf>) -> Self {
+        Self { out_dir, ..self }
+    }
+
+    /// Consumes the Packer and sets the path the archive is written to.
+    pub fn with_out(self, out: Option<PathBuf>) -> Self {
+        Self { out, ..self }
+    }
+
+    /// Returns the files matching the manifest's `include` globs, minus those matching its `exclude`
+    /// patterns or a line of `.hermignore`, the manifest and the mapping inputs, which are archived anyway.
+    /// Package archives, the one being written included, are never included.
+    ///
+    /// Returns paths relative to the package directory, `/` separated and sorted, as a Result.
+    fn included_files(&self, manifest: &Manifest, archive_path: &Path) -> Result<Vec<String>> {
+        if manifest.include.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut include = GlobSetBuilder::new();
+        for pattern in &manifest.include {
+            include.add(
+                GlobBuilder::new(pattern)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| {
+                        HermioneError::Manifest(format!("Invalid include glob {}: {}", pattern, e))
+                    })?,
+            );
+        }
+        let include = include.build()?;
+
+        let mut exclude = GitignoreBuilder::new(&self.package_path_buf);
+        for pattern in &manifest.exclude {
+            exclude.add_line(None, pattern).map_err(|e| {
+                HermioneError::Manifest(format!("Invalid exclude pattern {}: {}", pattern, e))
+            })?;
+        }
+        let hermignore = self.package_path_buf.join(HERMIGNORE);
+        if hermignore.is_file() {
+            if let Some(e) = exclude.add(&hermignore) {
+                return Err(eyre!("Unable to read {}: {}", hermignore.display(), e));
+            }
+        }
+        let exclude = exclude.build()?;
+
+        let mut files = Vec::new();
+        for entry in WalkBuilder::new(&self.package_path_buf)
+            .standard_filters(false)
+            .build()
+        {
+            let entry = entry?;
+            if !entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+            {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(&self.package_path_buf)?;
+            let name = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let is_archive = name.ends_with(".hpkg")
+                || fs::canonicalize(entry.path()).ok().as_deref() == Some(archive_path);
+            if !is_archive
+                && include.is_match(relative)
+                && !exclude
+                    .matched_path_or_any_parents(relative, false)
+                    .is_ignore()
+                && !Manifest::manifest_file_names().contains(&name.as_str())
+                && !manifest
+                    .mappings
+                    .iter()
+                    .any(|mapping| mapping.i.trim_start_matches("./") == name)
+            {
+                files.push(name);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Returns the timestamp entries are dated with when packing deterministically, None otherwise.
+    fn source_date_epoch(&self) -> Result<Option<u64>> {
+        match env::var(SOURCE_DATE_EPOCH) {
+            Ok(epoch) if !epoch.is_empty() => Ok(Some(epoch.parse().wrap_err_with(|| {
+                format!(
+                    "{} must be a Unix timestamp, not {}",
+                    SOURCE_DATE_EPOCH, epoch
+                )
+            })?)),
+            _ if self.deterministic => Ok(Some(0)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Reads the manifest embedded in a package archive without unpacking it.
+    ///
+    /// Errors if the archive holds no manifest or more than one.
+    pub fn get_manifest_from_archive(&self) -> Result<Manifest> {
         let archive_file = fs::File::open(&self.package_path_buf)?;
         let decoder = GzDecoder::new(archive_file);
         let mut archive = Archive::new(decoder);
-        if let Some(file_path) = archive
-            .entries()?
-            .filter_map(|entry| entry.ok())
-            .find(|entry| match entry.path() {
-                Ok(path) => {
-                    if let Some(file_name) = path.file_name() {
-                        file_name == Manifest::manifest_file_name().as_str()
-                    } else {
-                        false
-                    }
-                }
-                Err(_) => false,
-            })
-        {
-            Manifest::new_from_reader(file_path)
-        } else {
-            Err(eyre!("Could not find manifest file in archive"))
+        let mut manifests = Vec::new();
+        for entry in archive.entries()?.filter_map(|entry| entry.ok()) {
+            let path = match entry.path() {
+                Ok(path) => path.to_path_buf(),
+                Err(_) => continue,
+            };
+            // Only the top level holds the manifest, like Manifest::path_in expects of a package
+            // directory; included files may carry the same name deeper down.
+            let components = path
+                .components()
+                .filter(|component| *component != Component::CurDir)
+                .collect::<Vec<_>>();
+            let is_manifest = match components.as_slice() {
+                [Component::Normal(file_name)] => Manifest::manifest_file_names()
+                    .iter()
+                    .any(|manifest_file_name| file_name == manifest_file_name),
+                _ => false,
+            };
+            if is_manifest {
+                let mut contents = Vec::new();
+                let mut entry = entry;
+                entry.read_to_end(&mut contents)?;
+                manifests.push((path, contents));
+            }
+        }
+        match manifests.as_slice() {
+            [(path, contents)] => Manifest::new_from_reader_with_format(
+                contents.as_slice(),
+                ManifestFormat::from_path(path),
+            ),
+            [] => Err(HermioneError::Manifest(format!(
+                "Could not find manifest file in {}",
+                self.package_path_buf.display()
+            ))
+            .into()),
+            _ => Err(HermioneError::Manifest(format!(
+                "Found more than one manifest in {}: {}",
+                self.package_path_buf.display(),
+                manifests
+                    .iter()
+                    .map(|(path, _)| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+            .into()),
         }
     }
 
+    /// Packs the package directory into an archive, recording the integrity of every mapping input
+    /// in the archived manifest. The source manifest is left alone unless `with_write_integrity` is set.
+    ///
+    /// Returns the absolute path of the archive as a Result.
     pub fn pack(self) -> Result<String> {
         if self.package_path_buf.is_dir() {
-            let mut logger = Logger::new();
+            let manifest_path = Manifest::path_in(&self.package_path_buf)?;
 
-            let manifest_path = self.get_manifest_path_buf(&self.package_path_buf)?;
-
-            logger.loading("Loading package manifest");
+            self.events.emit(Event::info("Loading package manifest"));
             let manifest = Manifest::new_from_path(manifest_path.to_path_buf())?;
-            logger.info("Loaded package manifest.").newline(1);
+            self.events.emit(Event::info("Loaded package manifest."));
 
             // Create archive container for files
-            let archive_file_location = format!("{}_{}.hpkg", manifest.id, manifest.version);
-            let archive_file = fs::File::create(&archive_file_location)?;
-            let encoder = GzEncoder::new(archive_file, Compression::best());
-            let mut builder = Builder::new(encoder);
+            let archive_file_location = match (&self.out, &self.out_dir) {
+                (Some(out), _) => out.to_path_buf(),
+                (None, out_dir) => out_dir
+                    .as_deref()
+                    .unwrap_or_else(|| Path::new("."))
+                    .join(format!("{}_{}.hpkg", manifest.id, manifest.version)),
+            };
+            let archive_dir = match archive_file_location.parent() {
+                Some(parent) if parent != Path::new("") => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            fs::create_dir_all(&archive_dir)?;
+            let archive_file_location = fs::canonicalize(&archive_dir)?.join(
+                archive_file_location.file_name().ok_or_else(|| {
+                    eyre!("{} is not a file path", archive_file_location.display())
+                })?,
+            );
 
             // Loop through mappings, generate integrity and create mappings vec
-            logger.info(format!("Packaging {}", self.package_path_buf.display()));
+            self.events.emit(Event::info(format!(
+                "Packaging {}",
+                self.package_path_buf.display()
+            )));
             let mut mappings = Vec::new();
+            let mut entries = Vec::new();
             for file_mapping_definition in manifest.mappings.clone() {
                 let new_file_mapping_definition = file_mapping_definition
                     .with_integrity_set(self.package_path_buf.to_path_buf())?;
                 let file_path = self.package_path_buf.join(&new_file_mapping_definition.i);
-                builder.append_path_with_name(&file_path, &new_file_mapping_definition.i)?;
-                logger.indent(1).log(format!(
-                    "Added <blue>{}</> to package archive",
-                    new_file_mapping_definition.i
-                ));
+                entries.push(PackEntry {
+                    name: new_file_mapping_definition.i.clone(),
+                    path: file_path,
+                    contents: None,
+                });
                 mappings.push(new_file_mapping_definition);
             }
 
-            let changed_manifest = manifest.set_mappings(mappings);
-            let manifest_yaml = serde_yaml::to_string(&changed_manifest)?;
-            fs::write(&manifest_path, manifest_yaml)?;
-            logger.info(format!(
-                "Wrote integrity data to {}",
-                manifest_path.display()
-            ));
-            builder.append_path_with_name(&manifest_path, Manifest::manifest_file_name())?;
-            builder.into_inner()?;
-            logger.indent(1).log(format!(
-                "Added <blue>{}</> to package archive",
-                manifest_path
-                    .file_name()
-                    .unwrap_or(OsStr::new(&Manifest::manifest_file_name().as_str()))
-                    .to_string_lossy()
-            ));
-
-            let loc = fs::canonicalize(Path::new(&archive_file_location))?;
+            let mut files = BTreeMap::new();
+            for file in self.included_files(&manifest, &archive_file_location)? {
+                let file_path = self.package_path_buf.join(&file);
+                files.insert(
+                    file.clone(),
+                    Integrity::from(&fs::read(&file_path)?).to_string(),
+                );
+                entries.push(PackEntry {
+                    name: file,
+                    path: file_path,
+                    contents: None,
+                });
+            }
+
+            let changed_manifest = Manifest {
+                files,
+                ..manifest.set_mappings(mappings)
+            };
+            if self.write_integrity {
+                changed_manifest.store(&manifest_path)?;
+                self.events.emit(Event::info(format!(
+                    "Wrote integrity data to {}",
+                    manifest_path.display()
+                )));
+            }
+            let manifest_file_name = manifest_path
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().into_owned())
+                .unwrap_or_else(Manifest::manifest_file_name);
+            entries.push(PackEntry {
+                name: manifest_file_name,
+                contents: Some(
+                    ManifestFormat::from_path(&manifest_path)
+                        .serialize(&changed_manifest)?
+                        .into_bytes(),
+                ),
+                path: manifest_path,
+            });
+
+            // The archive is written to a temporary file and moved into place once complete,
+            // so it never ends up inside itself when written to the package directory
+            let archive_file = tempfile::Builder::new()
+                .prefix(".hpkg")
+                .tempfile_in(&archive_dir)
+                .wrap_err_with(|| {
+                    format!(
+                        "Unable to create archive {}",
+                        archive_file_location.display()
+                    )
+                })?;
+            let encoder = GzEncoder::new(archive_file.reopen()?, Compression::best());
+            let mut builder = Builder::new(encoder);
+
+            let mtime = self.source_date_epoch()?;
+            if mtime.is_some() {
+                entries.sort();
+                entries.dedup();
+            }
+            for entry in &entries {
+                match mtime {
+                    Some(mtime) => entry.append_normalized(&mut builder, mtime)?,
+                    None => entry.append(&mut builder)?,
+                }
+                self.events.emit(Event::detail(format!(
+                    "Added <blue>{}</> to package archive",
+                    entry.name
+                )));
+            }
+            builder.into_inner()?.finish()?;
+            archive_file
+                .persist(&archive_file_location)
+                .wrap_err_with(|| {
+                    format!(
+                        "Unable to write archive {}",
+                        archive_file_location.display()
+                    )
+                })?;
+
+            // Temporary files are only readable by their owner, archives are meant to be shared
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+
+                fs::set_permissions(&archive_file_location, fs::Permissions::from_mode(0o644))?;
+            }
+            let loc = fs::canonicalize(&archive_file_location)?;
             Ok(format!("{}", loc.to_string_lossy()))
         } else {
             Err(eyre!(
@@ -115,14 +377,128 @@ impl Packer {
         }
     }
 
+    /// Reads a package archive without unpacking it: lists its entries, checks the integrity
+    /// of every mapping input against the archived contents and finds files no mapping refers to.
+    ///
+    /// Returns an Inspection as a Result.
+    pub fn inspect(&self) -> Result<Inspection> {
+        let manifest = self.get_manifest_from_archive()?;
+
+        let archive_file = fs::File::open(&self.package_path_buf)?;
+        let mut archive = Archive::new(GzDecoder::new(archive_file));
+        let mut entries = Vec::new();
+        let mut contents = HashMap::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_string_lossy().into_owned();
+            let path = String::from(path.trim_start_matches("./"));
+            let header = entry.header();
+            let kind = if header.entry_type().is_file() {
+                EntryKind::File
+            } else if header.entry_type().is_dir() {
+                EntryKind::Directory
+            } else if header.entry_type().is_symlink() {
+                EntryKind::Symlink
+            } else {
+                EntryKind::Other
+            };
+            let size = header.size()?;
+            let mode = header.mode()? & 0o7777;
+            if kind == EntryKind::File {
+                let mut bytes = Vec::new();
+                entry.read_to_end(&mut bytes)?;
+                contents.insert(path.clone(), bytes);
+            }
+            entries.push(ArchiveEntry {
+                path,
+                kind,
+                size,
+                mode,
+            });
+        }
+
+        let mappings = manifest
+            .mappings
+            .iter()
+            .map(|definition| {
+                let input = String::from(definition.i.trim_start_matches("./"));
+                let state = match (contents.get(&input), &definition.integrity) {
+                    (None, _) => IntegrityState::Missing,
+                    (Some(_), None) => IntegrityState::Unrecorded,
+                    (Some(bytes), Some(_)) => {
+                        if definition.verify_integrity_of_contents(bytes)? {
+                            IntegrityState::Ok
+                        } else {
+                            IntegrityState::Mismatch
+                        }
+                    }
+                };
+                Ok(MappingIntegrity {
+                    input,
+                    integrity: definition.integrity.clone(),
+                    state,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let files = manifest
+            .files
+            .iter()
+            .map(|(path, integrity)| {
+                let state = match contents.get(path) {
+                    None => IntegrityState::Missing,
+                    Some(bytes) => {
+                        if manifest.verify_file_contents(path, bytes)? {
+                            IntegrityState::Ok
+                        } else {
+                            IntegrityState::Mismatch
+                        }
+                    }
+                };
+                Ok(MappingIntegrity {
+                    input: path.clone(),
+                    integrity: Some(integrity.clone()),
+                    state,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let manifest_text = Manifest::manifest_file_names()
+            .iter()
+            .find_map(|file_name| contents.get(*file_name))
+            .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+            .unwrap_or_default();
+
+        let unreferenced = entries
+            .iter()
+            .filter(|entry| entry.kind == EntryKind::File)
+            .map(|entry| entry.path.clone())
+            .filter(|path| {
+                !Manifest::manifest_file_names().contains(&path.as_str())
+                    && !mappings.iter().any(|mapping| &mapping.input == path)
+                    && !manifest.files.contains_key(path)
+            })
+            .collect();
+
+        Ok(Inspection {
+            entries,
+            manifest,
+            manifest_text,
+            mappings,
+            files,
+            unreferenced,
+        })
+    }
+
     pub fn unpack(self, dest: PathBuf) -> Result<PathBuf> {
-        let mut logger = Logger::new();
-        logger.loading(format!(
+        self.events.emit(Event::info(format!(
             "Starting unpacking package path {}",
             &self.package_path_buf.display()
-        ));
+        )));
 
-        logger.info("Ingesting manifest file from archive in memory");
+        self.events.emit(Event::info(
+            "Ingesting manifest file from archive in memory",
+        ));
         let archive_manifest_file = self.get_manifest_from_archive();
 
         let archive_file = fs::File::open(&self.package_path_buf)?;
@@ -132,15 +508,242 @@ impl Packer {
         match archive_manifest_file {
             Ok(manifest_file) => {
                 let final_dest = dest.join(manifest_file.id);
-                logger.indent(1).log(format!(
+                self.events.emit(Event::detail(format!(
                     "Unpacking package in directory: {}",
                     final_dest.display()
-                ));
+                )));
                 archive.unpack(&final_dest)?;
-                logger.success("Finished unpacking");
+                self.events.emit(Event::success("Finished unpacking"));
                 Ok(final_dest)
             }
             Err(e) => Err(e),
         }
     }
 }
+
+/// A file to archive.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct PackEntry {
+    /// Path of the entry inside the archive.
+    name: String,
+    /// File on disk the entry is read from.
+    path: PathBuf,
+    /// Contents replacing those of the file on disk, keeping its metadata.
+    contents: Option<Vec<u8>>,
+}
+
+impl PackEntry {
+    /// Appends the entry with the metadata of the file on disk.
+    fn append<W: Write>(&self, builder: &mut Builder<W>) -> Result<()> {
+        match &self.contents {
+            Some(contents) => {
+                let mut header = Header::new_gnu();
+                header.set_metadata(&fs::metadata(&self.path)?);
+                header.set_size(contents.len() as u64);
+                builder.append_data(&mut header, &self.name, contents.as_slice())?;
+            }
+            None => builder.append_path_with_name(&self.path, &self.name)?,
+        }
+        Ok(())
+    }
+
+    /// Appends the entry with normalized metadata, so it only depends on its name,
+    /// its contents, whether it is executable and the given timestamp.
+    fn append_normalized<W: Write>(&self, builder: &mut Builder<W>, mtime: u64) -> Result<()> {
+        let contents = match &self.contents {
+            Some(contents) => contents.clone(),
+            None => fs::read(&self.path)?,
+        };
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Regular);
+        header.set_size(contents.len() as u64);
+        header.set_mode(if is_executable(&self.path)? {
+            0o755
+        } else {
+            0o644
+        });
+        header.set_mtime(mtime);
+        header.set_uid(0);
+        header.set_gid(0);
+        builder.append_data(&mut header, &self.name, contents.as_slice())?;
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+
+    Ok(fs::metadata(path)?.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> Result<bool> {
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_inspect_flags_mismatches_and_unreferenced_files() {
+        let temp_dir = TempDir::new().expect("Unable to create temp dir in test");
+        let manifest = fs::read_to_string("example-package/hermione.yml")
+            .expect("Unable to read example manifest in test");
+        let archive_path = temp_dir.path().join("example.hpkg");
+        let archive_file =
+            fs::File::create(&archive_path).expect("Unable to create archive in test");
+        let mut builder = Builder::new(GzEncoder::new(archive_file, Compression::best()));
+        for (path, contents) in &[
+            ("hermione.yml", manifest.as_str()),
+            ("a.txt", ""),
+            ("b.txt", "tampered"),
+            ("notes.txt", "not mapped"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, contents.as_bytes())
+                .expect("Unable to append to archive in test");
+        }
+        builder
+            .into_inner()
+            .expect("Unable to finish archive in test")
+            .finish()
+            .expect("Unable to finish archive in test");
+
+        let inspection = Packer::new(archive_path)
+            .inspect()
+            .expect("Unable to inspect archive in test");
+
+        assert_eq!(4, inspection.entries.len());
+        assert_eq!(manifest, inspection.manifest_text);
+        assert_eq!(0o644, inspection.entries[0].mode);
+        assert_eq!(IntegrityState::Ok, inspection.mappings[0].state);
+        assert_eq!(IntegrityState::Mismatch, inspection.mappings[1].state);
+        assert_eq!(vec![String::from("notes.txt")], inspection.unreferenced);
+        assert_eq!(1, inspection.failures().len());
+    }
+
+    #[test]
+    fn test_pack_includes_files_and_records_their_integrity() {
+        let package_dir = TempDir::new().expect("Unable to create temp dir in test");
+        let package_path = package_dir.path();
+        for (path, contents) in &[
+            (
+                "hermione.yml",
+                "---
+name: Dots
+authors: []
+description: My dotfiles
+id: org.example.dots
+version: 0.1.0
+include:
+  - \"scripts/*.sh\"
+  - README.md
+  - \"docs/**\"
+exclude:
+  - \"*.bak\"
+mappings:
+  - i: a.txt
+    o: \"{{HOME}}/a.txt\"
+",
+            ),
+            (".hermignore", "# drafts stay home\ndocs/drafts/\n"),
+            ("a.txt", "a"),
+            ("README.md", "readme"),
+            ("scripts/setup.sh", "echo setup"),
+            ("scripts/nested/deep.sh", "echo deep"),
+            ("docs/usage.md", "usage"),
+            ("docs/usage.md.bak", "old usage"),
+            ("docs/drafts/next.md", "next"),
+        ] {
+            let path = package_path.join(path);
+            fs::create_dir_all(path.parent().expect("No parent in test"))
+                .expect("Unable to create dir in test");
+            fs::write(path, contents).expect("Unable to write file in test");
+        }
+        let out_dir = TempDir::new().expect("Unable to create temp dir in test");
+        let archive_path = out_dir.path().join("dots.hpkg");
+
+        Packer::new(package_path.to_path_buf())
+            .with_out(Some(archive_path.clone()))
+            .pack()
+            .expect("Unable to pack in test");
+        let inspection = Packer::new(archive_path)
+            .inspect()
+            .expect("Unable to inspect archive in test");
+
+        assert_eq!(
+            vec!["README.md", "docs/usage.md", "scripts/setup.sh"],
+            inspection
+                .files
+                .iter()
+                .map(|file| file.input.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert!(inspection
+            .files
+            .iter()
+            .all(|file| file.state == IntegrityState::Ok));
+        assert_eq!(3, inspection.manifest.files.len());
+        assert!(inspection.unreferenced.is_empty());
+        assert_eq!(5, inspection.entries.len());
+    }
+
+    #[test]
+    fn test_pack_includes_nested_manifest_names_as_plain_files() {
+        let package_dir = TempDir::new().expect("Unable to create temp dir in test");
+        let package_path = package_dir.path();
+        for (path, contents) in &[
+            (
+                "hermione.yml",
+                "---
+name: Dots
+authors: []
+description: My dotfiles
+id: org.example.dots
+version: 0.1.0
+include:
+  - \"docs/**\"
+mappings: []
+",
+            ),
+            ("docs/hermione.yml", "name: Example"),
+            ("docs/nested/hermione.json", "{}"),
+        ] {
+            let path = package_path.join(path);
+            fs::create_dir_all(path.parent().expect("No parent in test"))
+                .expect("Unable to create dir in test");
+            fs::write(path, contents).expect("Unable to write file in test");
+        }
+        let out_dir = TempDir::new().expect("Unable to create temp dir in test");
+        let archive_path = out_dir.path().join("dots.hpkg");
+
+        Packer::new(package_path.to_path_buf())
+            .with_out(Some(archive_path.clone()))
+            .pack()
+            .expect("Unable to pack in test");
+        let packer = Packer::new(archive_path);
+        let manifest = packer
+            .get_manifest_from_archive()
+            .expect("Unable to read archived manifest in test");
+        let inspection = packer.inspect().expect("Unable to inspect archive in test");
+
+        assert_eq!("org.example.dots", manifest.id);
+        assert_eq!(
+            vec!["docs/hermione.yml", "docs/nested/hermione.json"],
+            inspection
+                .files
+                .iter()
+                .map(|file| file.input.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert!(inspection.unreferenced.is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+use color_eyre::eyre::{Result, WrapErr};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::HermioneError;
+use crate::package_service::PackageService;
+use crate::plan::PlannedOperation;
+
+const PINS_FILE_NAME: &str = "pins.yml";
+
+/// Pins hold packages back from upgrades. Each pinned package id maps to the semver
+/// requirement its version has to keep meeting, `=<version>` for packages held where they are.
+/// They are stored in the install directory.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pins {
+    pins: BTreeMap<String, String>,
+}
+
+impl Pins {
+    /// Returns the path of the pins file.
+    pub fn path(package_service: &PackageService) -> PathBuf {
+        package_service.install_dir().join(PINS_FILE_NAME)
+    }
+
+    /// Loads the pins, which are empty when nothing was pinned yet.
+    pub fn load(package_service: &PackageService) -> Result<Self> {
+        let path = Self::path(package_service);
+        if path.is_file() {
+            let yaml = fs::read_to_string(&path)?;
+            serde_yaml::from_str(&yaml)
+                .wrap_err_with(|| format!("Could not parse pins {}", path.display()))
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Writes the pins back to the install directory.
+    pub fn store(&self, package_service: &PackageService) -> Result<()> {
+        let path = Self::path(package_service);
+        let yaml = serde_yaml::to_string(self)?;
+        package_service.perform(PlannedOperation::Write { path: path.clone() }, || {
+            Ok(fs::write(&path, yaml)?)
+        })
+    }
+
+    /// Returns the requirement a package is pinned to, if it is pinned.
+    pub fn get(&self, package_id: &str) -> Option<&String> {
+        self.pins.get(package_id)
+    }
+
+    /// Pins a package to a requirement, replacing any previous pin.
+    ///
+    /// Errors if the requirement is not valid semver.
+    pub fn pin(&mut self, package_id: &str, version_req: &str) -> Result<()> {
+        VersionReq::parse(version_req).map_err(|e| {
+            HermioneError::Policy(format!(
+                "Invalid version requirement {}: {}",
+                version_req, e
+            ))
+        })?;
+        self.pins
+            .insert(String::from(package_id), String::from(version_req));
+        Ok(())
+    }
+
+    /// Unpins a package.
+    ///
+    /// Returns the requirement it was pinned to, if it was pinned.
+    pub fn unpin(&mut self, package_id: &str) -> Option<String> {
+        self.pins.remove(package_id)
+    }
+
+    /// Returns true unless the package is pinned to a requirement the version does not meet.
+    pub fn allows(&self, package_id: &str, version: &str) -> Result<bool> {
+        match self.get(package_id) {
+            Some(version_req) => {
+                Ok(VersionReq::parse(version_req)?.matches(&Version::parse(version)?))
+            }
+            None => Ok(true),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows() {
+        let mut pins = Pins::default();
+        pins.pin("org.example.held", "=1.0.0")
+            .expect("Unable to pin in test");
+        pins.pin("org.example.minor", "~1.2")
+            .expect("Unable to pin in test");
+
+        assert!(!pins
+            .allows("org.example.held", "1.0.1")
+            .expect("Unable to check pin in test"));
+        assert!(pins
+            .allows("org.example.minor", "1.2.7")
+            .expect("Unable to check pin in test"));
+        assert!(!pins
+            .allows("org.example.minor", "1.3.0")
+            .expect("Unable to check pin in test"));
+        assert!(pins
+            .allows("org.example.other", "9.0.0")
+            .expect("Unable to check pin in test"));
+        assert!(pins.pin("org.example.bogus", "not a version").is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use color_eyre::eyre::Result;
+use serde::Serialize;
+use tempfile::TempDir;
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// A filesystem change or hook execution Hermione intends to make.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum PlannedOperation {
+    CreateDir { path: PathBuf },
+    Copy { from: PathBuf, to: PathBuf },
+    Link { path: PathBuf, target: PathBuf },
+    Unlink { path: PathBuf },
+    Backup { path: PathBuf },
+    Restore { backup_id: String, path: PathBuf },
+    Remove { path: PathBuf },
+    Write { path: PathBuf },
+    RunHook { hook: String },
+}
+
+impl fmt::Display for PlannedOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlannedOperation::CreateDir { path } => {
+                write!(f, "create directory {}", path.display())
+            }
+            PlannedOperation::Copy { from, to } => {
+                write!(f, "copy {} -> {}", from.display(), to.display())
+            }
+            PlannedOperation::Link { path, target } => {
+                write!(f, "link {} -> {}", path.display(), target.display())
+            }
+            PlannedOperation::Unlink { path } => write!(f, "unlink {}", path.display()),
+            PlannedOperation::Backup { path } => write!(f, "back up {}", path.display()),
+            PlannedOperation::Restore { backup_id, path } => {
+                write!(f, "restore {} from backup {}", path.display(), backup_id)
+            }
+            PlannedOperation::Remove { path } => write!(f, "remove {}", path.display()),
+            PlannedOperation::Write { path } => write!(f, "write {}", path.display()),
+            PlannedOperation::RunHook { hook } => write!(f, "run {} hook", hook),
+        }
+    }
+}
+
+/// Plan collects the operations of a dry run instead of performing them.
+/// Packages are downloaded into a throwaway staging directory so that
+/// a dry run leaves the download cache untouched as well.
+#[derive(Clone, Debug)]
+pub struct Plan {
+    operations: Arc<Mutex<Vec<PlannedOperation>>>,
+    staging_dir: Arc<TempDir>,
+}
+
+impl Plan {
+    pub fn new() -> Result<Self> {
+        let staging_dir = tempfile::Builder::new()
+            .prefix("hermione_dry_run_")
+            .tempdir()?;
+        Ok(Self {
+            operations: Arc::new(Mutex::new(Vec::new())),
+            staging_dir: Arc::new(staging_dir),
+        })
+    }
+
+    /// Adds an operation to the plan.
+    pub fn record(&self, operation: PlannedOperation) {
+        self.operations
+            .lock()
+            .expect("Unable to record planned operation")
+            .push(operation);
+    }
+
+    /// Returns the recorded operations in order.
+    pub fn operations(&self) -> Vec<PlannedOperation> {
+        self.operations
+            .lock()
+            .expect("Unable to read planned operations")
+            .clone()
+    }
+
+    /// Returns the directory packages are downloaded into during the dry run.
+    pub fn staging_dir(&self) -> PathBuf {
+        self.staging_dir.path().to_path_buf()
+    }
+
+    /// Returns the plan as a JSON array.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.operations())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_is_shared_between_clones() {
+        let plan = Plan::new().expect("Unable to create plan in test");
+        let cloned = plan.clone();
+        cloned.record(PlannedOperation::RunHook {
+            hook: String::from("pre_install"),
+        });
+        assert_eq!(1, plan.operations().len());
+        assert!(plan
+            .to_json()
+            .expect("Unable to serialize plan in test")
+            .contains("\"operation\": \"run_hook\""));
+    }
+}
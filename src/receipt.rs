@@ -0,0 +1,85 @@
+use chrono::Local;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const RECEIPT_FILE_NAME: &str = ".hermione-receipt.yml";
+
+/// Receipt records where a package came from and when it was installed.
+/// It is written next to the downloaded package and travels with it into the install directory.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Receipt {
+    /// Source the package was downloaded from, as an absolute URL.
+    pub source: String,
+    /// RFC 3339 timestamp of when the package was downloaded.
+    pub downloaded_at: String,
+    /// RFC 3339 timestamp of when the package was installed.
+    pub installed_at: Option<String>,
+    /// Subresource integrity of the archive the package was unpacked from, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
+    /// Mapping outputs left alone at install because they already existed and the
+    /// conflict strategy was `skip`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<PathBuf>,
+}
+
+impl Receipt {
+    /// Returns a new Receipt for a package which was just downloaded.
+    pub fn new(source: String) -> Self {
+        Self {
+            source,
+            downloaded_at: Local::now().to_rfc3339(),
+            installed_at: None,
+            integrity: None,
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Consumes the Receipt and records the integrity of the package archive.
+    pub fn with_integrity(self, integrity: Option<String>) -> Self {
+        Self { integrity, ..self }
+    }
+
+    /// Consumes the Receipt and records the mapping outputs which were skipped at install.
+    pub fn with_skipped(self, skipped: Vec<PathBuf>) -> Self {
+        Self { skipped, ..self }
+    }
+
+    /// Consumes the Receipt and marks it as installed now.
+    pub fn installed_now(self) -> Self {
+        Self {
+            installed_at: Some(Local::now().to_rfc3339()),
+            ..self
+        }
+    }
+
+    /// Returns the path of the Receipt inside a package directory.
+    pub fn receipt_path(package_path: &Path) -> PathBuf {
+        package_path.join(RECEIPT_FILE_NAME)
+    }
+
+    /// Loads the Receipt stored in a package directory.
+    pub fn load(package_path: &Path) -> Result<Self> {
+        let receipt_path = Self::receipt_path(package_path);
+        if receipt_path.is_file() {
+            let yaml = fs::read_to_string(&receipt_path)?;
+            serde_yaml::from_str(&yaml)
+                .wrap_err_with(|| format!("Could not parse receipt {}", receipt_path.display()))
+        } else {
+            Err(eyre!(
+                "No receipt found in {}, try reinstalling the package",
+                package_path.display()
+            ))
+        }
+    }
+
+    /// Writes the Receipt into a package directory.
+    pub fn store(&self, package_path: &Path) -> Result<()> {
+        let yaml = serde_yaml::to_string(self)?;
+        fs::write(Self::receipt_path(package_path), yaml)?;
+        Ok(())
+    }
+}
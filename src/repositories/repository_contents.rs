@@ -4,7 +4,7 @@ use crate::repositories::available_package::AvailablePackage;
 
 #[derive(Deserialize, Serialize)]
 pub struct RepositoryContents {
-  pub name: String,
-  pub url: String,
-  pub available_packages: Vec<AvailablePackage>,
+    pub name: String,
+    pub url: String,
+    pub available_packages: Vec<AvailablePackage>,
 }
@@ -1,18 +1,22 @@
 use color_eyre::eyre::{eyre, Result};
-use paris::Logger;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use crate::events::{self, Event, EventSink};
 use crate::file_mapping_definition::FileMappingDefinition;
 use crate::hooks::Hooks;
-use crate::manifest::Manifest;
+use crate::manifest::{Manifest, ManifestFormat, MANIFEST_VERSION};
 
 /// Scaffold is responsible for creating `hermione.yml` files and new Hermione package directories
 pub struct Scaffold {
     pub manifest: Manifest,
     pub package_path_buf: PathBuf,
+    /// File format the manifest is written in, YAML unless set with `with_format`.
+    pub format: ManifestFormat,
+    events: Arc<dyn EventSink>,
 }
 
 impl Scaffold {
@@ -31,27 +35,36 @@ impl Scaffold {
         };
 
         Self {
+            events: events::silent(),
+            format: ManifestFormat::Yaml,
             package_path_buf: package_path.to_path_buf(),
             manifest: Manifest {
+                manifest_version: Some(MANIFEST_VERSION),
                 authors: default_authors,
                 name: parsed_package_name,
                 description: default_description,
                 id: String::from(package_id),
                 version: String::from("0.1.0"),
+                min_herm_version: None,
+                include: Vec::new(),
+                exclude: Vec::new(),
                 mappings: vec![
                     FileMappingDefinition::new(
                         String::from("sample.txt"),
                         String::from("{{HOME}}/sample.txt"),
                         None,
                         None,
+                        None,
                     ),
                     FileMappingDefinition::new(
                         String::from("config.toml"),
                         String::from("/tmp/absolute/path/to/dir/config.toml"),
                         Some(String::from("unix")),
                         None,
+                        None,
                     ),
                 ],
+                files: BTreeMap::new(),
                 hooks: Some(Hooks {
                     pre_install: Some(String::from("echo Hello from pre-install hook")),
                     post_install: Some(String::from("echo Hello from post-install hook")),
@@ -62,72 +75,81 @@ impl Scaffold {
         }
     }
 
+    /// Consumes the Scaffold and sets the sink it reports progress to.
+    pub fn with_events(self, events: Arc<dyn EventSink>) -> Self {
+        Self { events, ..self }
+    }
+
+    /// Consumes the Scaffold and sets the file format the manifest is written in.
+    pub fn with_format(self, format: ManifestFormat) -> Self {
+        Self { format, ..self }
+    }
+
     /// Creates a package directory with a sample `hermione.yml` file and a couple of sample files to correspond with it.
     pub fn create_package(&self) -> Result<()> {
-        let mut logger = Logger::new();
-        logger.info(format!(
+        self.events.emit(Event::info(format!(
             "Creating package directory {}",
             &self.manifest.name
-        ));
+        )));
 
         match fs::create_dir_all(&self.package_path_buf) {
             Ok(_) => {
-                logger.info(format!(
+                self.events.emit(Event::info(format!(
                     "Successfully created package directory {}",
                     self.manifest.name.clone()
-                ));
+                )));
                 self.create_manifest(self.package_path_buf.to_path_buf())?;
                 self.create_example_files()?;
                 Ok(())
             }
             Err(e) => {
-                logger.error(format!(
+                self.events.emit(Event::error(format!(
                     "Could not create package directory {}",
                     self.package_path_buf.display()
-                ));
+                )));
                 Err(eyre!(e))
             }
         }
     }
 
-    /// Creates the `hermione.yml` file from the Manifest struct and writes it to the given PathBuf.
+    /// Creates the manifest file from the Manifest struct in the Scaffold's format and writes it to the given PathBuf.
+    /// Refuses when the directory already has a manifest in any format.
     ///
     /// ### Arguments
     ///
-    /// * path - PathBuf of where to write the `hermione.yml` file
-    /// * logger - Borrowed Logger instance
+    /// * path - PathBuf of where to write the manifest file
     ///
     /// Returns an Empty Result.
     pub fn create_manifest(&self, path: PathBuf) -> Result<()> {
-        let mut logger = Logger::new();
-        logger.info("Creating manifest file");
-
-        let hermione_string = serde_yaml::to_string(&self.manifest)?;
-        let hermione_manifest_path = path.join("hermione.yml");
-
-        if hermione_manifest_path.is_file() {
-            logger.error("hermione.yml already exists in current directory, will not overwrite");
-            Err(eyre!("hermione.yml exists in current directory"))
+        self.events.emit(Event::info("Creating manifest file"));
+
+        let hermione_string = self.format.serialize(&self.manifest)?;
+        let hermione_manifest_path = path.join(self.format.file_name());
+        let existing = Manifest::manifest_file_names()
+            .iter()
+            .find(|file_name| path.join(file_name).is_file());
+
+        if let Some(existing) = existing {
+            self.events.emit(Event::error(format!(
+                "{} already exists in current directory, will not overwrite",
+                existing
+            )));
+            Err(eyre!("{} exists in current directory", existing))
         } else {
             fs::write(&hermione_manifest_path, hermione_string)?;
-            logger.info(format!(
+            self.events.emit(Event::info(format!(
                 "Successfully created hermione manifest file {}",
                 hermione_manifest_path.display()
-            ));
+            )));
             Ok(())
         }
     }
 
     /// Creates the Hermione sample files.
     ///
-    /// ### Arguments
-    ///
-    /// * logger - Borrowed Logger instance
-    ///
     /// Returns an Empty Result.
     fn create_example_files(&self) -> Result<()> {
-        let mut logger = Logger::new();
-        logger.loading("Creating example files");
+        self.events.emit(Event::info("Creating example files"));
 
         let mut sample_files = HashMap::new();
 
@@ -141,7 +163,8 @@ impl Scaffold {
             fs::write(self.package_path_buf.join(file_name), file_content)?;
         }
 
-        logger.success("Finished creating example files");
+        self.events
+            .emit(Event::success("Finished creating example files"));
         Ok(())
     }
 }
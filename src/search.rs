@@ -0,0 +1,117 @@
+use color_eyre::eyre::Result;
+use semver::Version;
+use serde::Serialize;
+
+use std::fmt;
+
+use crate::config::HermioneConfig;
+use crate::package_service::PackageService;
+use crate::repositories::repository_contents::RepositoryContents;
+
+/// A package offered by a configured repository.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SearchResult {
+    pub id: String,
+    pub homepage: String,
+    /// Name of the repository offering the package.
+    pub repository: String,
+    /// Newest semver version the repository lists, if any.
+    pub latest: Option<String>,
+    /// Source URL of the newest version.
+    pub url: Option<String>,
+}
+
+impl fmt::Display for SearchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} <blue>{}</> ({}) {}",
+            self.id,
+            self.latest.as_deref().unwrap_or("?"),
+            self.repository,
+            self.homepage
+        )
+    }
+}
+
+/// Searches the configured repositories for packages whose id contains the query, ignoring case.
+///
+/// Returns the matching packages ordered by id as a Result.
+pub fn search(package_service: &PackageService, query: &str) -> Result<Vec<SearchResult>> {
+    let repositories = HermioneConfig::load(&package_service.config_dir())?
+        .available_repositories(package_service.events.as_ref())?;
+    Ok(matching(&repositories, query))
+}
+
+/// Returns the packages of the repositories whose id contains the query, ignoring case.
+fn matching(repositories: &[RepositoryContents], query: &str) -> Vec<SearchResult> {
+    let query = query.to_lowercase();
+    let mut results = repositories
+        .iter()
+        .flat_map(|repository| {
+            repository
+                .available_packages
+                .iter()
+                .map(move |available_package| (repository, available_package))
+        })
+        .filter(|(_, available_package)| available_package.id.to_lowercase().contains(&query))
+        .map(|(repository, available_package)| {
+            let newest = available_package
+                .available_versions
+                .iter()
+                .filter_map(|available_version| {
+                    Version::parse(&available_version.version)
+                        .ok()
+                        .map(|version| (version, available_version.url.clone()))
+                })
+                .max();
+            SearchResult {
+                id: available_package.id.clone(),
+                homepage: available_package.homepage.clone(),
+                repository: repository.name.clone(),
+                latest: newest.as_ref().map(|(version, _)| version.to_string()),
+                url: newest.map(|(_, url)| url),
+            }
+        })
+        .collect::<Vec<_>>();
+    results.sort_by(|a, b| a.id.cmp(&b.id));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_ids_and_picks_newest_version() {
+        let repositories: RepositoryContents = toml::from_str(
+            r#"
+name = "Example"
+url = "https://example.com/repository.toml"
+
+[[available_packages]]
+id = "org.example.dots"
+homepage = "https://example.com/dots"
+available_versions = [
+    { version = "0.10.0", url = "https://example.com/dots-0.10.0.hpkg" },
+    { version = "0.9.0", url = "https://example.com/dots-0.9.0.hpkg" },
+]
+
+[[available_packages]]
+id = "org.example.vim"
+homepage = "https://example.com/vim"
+available_versions = []
+"#,
+        )
+        .expect("Unable to parse repository in test");
+
+        let results = matching(&[repositories], "DOTS");
+
+        assert_eq!(1, results.len());
+        assert_eq!(Some(String::from("0.10.0")), results[0].latest);
+        assert_eq!(
+            Some(String::from("https://example.com/dots-0.10.0.hpkg")),
+            results[0].url
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use color_eyre::eyre::{Result, WrapErr};
+use semver::Version;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::events::Event;
+use crate::manifest::Manifest;
+use crate::package_service::PackageService;
+use crate::plan::PlannedOperation;
+
+#[cfg(target_family = "unix")]
+use std::os::unix::fs::symlink;
+
+#[cfg(target_family = "windows")]
+use std::os::windows::fs::symlink_dir;
+
+const CURRENT: &str = "current";
+
+/// Versions keeps every installed version of a package in `<install dir>/<id>/<version>`,
+/// with a `current` link pointing at the active one so older versions can be rolled back to.
+///
+/// Packages installed before versioned directories existed keep their files directly in
+/// `<install dir>/<id>`, which is treated as the current version until it is upgraded.
+pub struct Versions {
+    root: PathBuf,
+    package_service: PackageService,
+}
+
+impl Versions {
+    /// Returns the Versions of the package with the given id.
+    pub fn new(package_service: &PackageService, package_id: &str) -> Self {
+        Self::from_root(
+            package_service,
+            package_service.install_dir().join(package_id),
+        )
+    }
+
+    /// Returns the Versions kept in the given package directory.
+    pub fn from_root(package_service: &PackageService, root: PathBuf) -> Self {
+        Self {
+            root,
+            package_service: package_service.clone(),
+        }
+    }
+
+    /// Returns the directory holding every version of the package.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the directory of the given version.
+    pub fn path(&self, version: &str) -> PathBuf {
+        self.root.join(version)
+    }
+
+    /// Returns true for packages installed before versioned directories existed.
+    pub fn is_legacy(&self) -> bool {
+        Manifest::manifest_file_names()
+            .iter()
+            .any(|file_name| self.root.join(file_name).is_file())
+    }
+
+    /// Returns the name of the active version, if any.
+    pub fn current(&self) -> Option<String> {
+        fs::read_link(self.root.join(CURRENT))
+            .ok()
+            .and_then(|target| {
+                target
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+    }
+
+    /// Returns the directory of the active version, if any.
+    pub fn current_path(&self) -> Option<PathBuf> {
+        if self.is_legacy() {
+            return Some(self.root.clone());
+        }
+        self.current()
+            .map(|version| self.path(&version))
+            .filter(|path| path.is_dir())
+    }
+
+    /// Returns the kept versions, oldest first.
+    pub fn installed(&self) -> Result<Vec<String>> {
+        if !self.root.is_dir() || self.is_legacy() {
+            return Ok(Vec::new());
+        }
+        let mut versions = fs::read_dir(&self.root)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir() && entry.file_name() != CURRENT)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                Version::parse(&name).ok().map(|version| (version, name))
+            })
+            .collect::<Vec<_>>();
+        versions.sort();
+        Ok(versions.into_iter().map(|(_, name)| name).collect())
+    }
+
+    /// Points `current` at the given version.
+    ///
+    /// Returns an empty Result.
+    pub fn set_current(&self, version: &str) -> Result<()> {
+        let current = self.root.join(CURRENT);
+        self.package_service.perform(
+            PlannedOperation::Link {
+                path: current.clone(),
+                target: PathBuf::from(version),
+            },
+            || {
+                // Swap the link in with a rename so `current` never goes missing
+                let staged = self.root.join(format!(".{}", CURRENT));
+                if fs::symlink_metadata(&staged).is_ok() {
+                    fs::remove_file(&staged)?;
+                }
+
+                #[cfg(target_family = "windows")]
+                let link_result = symlink_dir(version, &staged);
+
+                #[cfg(target_family = "unix")]
+                let link_result = symlink(version, &staged);
+
+                link_result.wrap_err_with(|| {
+                    format!("Failed to link {} -> {}", current.display(), version)
+                })?;
+                fs::rename(&staged, &current)
+                    .wrap_err_with(|| format!("Failed to replace {}", current.display()))?;
+                Ok(())
+            },
+        )
+    }
+
+    /// Removes the oldest versions until at most `keep` versions besides the current one are left.
+    ///
+    /// Returns the removed versions as a Result.
+    pub fn prune(&self, keep: usize) -> Result<Vec<String>> {
+        let current = self.current();
+        let old = self
+            .installed()?
+            .into_iter()
+            .filter(|version| Some(version) != current.as_ref())
+            .collect::<Vec<_>>();
+        let excess = old.len().saturating_sub(keep);
+        let removed = old.into_iter().take(excess).collect::<Vec<_>>();
+        for version in &removed {
+            let path = self.path(version);
+            self.package_service.events.emit(Event::detail(format!(
+                "Removing old version {}",
+                path.display()
+            )));
+            self.package_service
+                .perform(PlannedOperation::Remove { path: path.clone() }, || {
+                    Ok(fs::remove_dir_all(&path)?)
+                })?;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_prune_keeps_current_and_newest() {
+        let temp_dir = TempDir::new().expect("Unable to create temp dir in test");
+        let package_service =
+            PackageService::new().expect("Unable to instantiate PackageService in test");
+        let versions = Versions::from_root(&package_service, temp_dir.path().to_path_buf());
+        for version in &["0.1.0", "0.10.0", "0.2.0", "1.0.0"] {
+            fs::create_dir(versions.path(version)).expect("Unable to create version in test");
+        }
+        versions
+            .set_current("0.1.0")
+            .expect("Unable to set current version in test");
+
+        let removed = versions.prune(1).expect("Unable to prune in test");
+
+        assert_eq!(vec!["0.2.0", "0.10.0"], removed);
+        assert_eq!(
+            vec!["0.1.0", "1.0.0"],
+            versions
+                .installed()
+                .expect("Unable to list versions in test")
+        );
+        assert_eq!(Some(String::from("0.1.0")), versions.current());
+    }
+}
@@ -0,0 +1,126 @@
+use assert_fs::TempDir;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use hermione::events::{Event, EventSink};
+use hermione::manifest::ManifestFormat;
+use hermione::{Manifest, PackageService};
+
+#[test]
+fn library_test_load_example_manifest() {
+    let manifest =
+        Manifest::new_from_path(Path::new("example-package").join(Manifest::manifest_file_name()))
+            .expect("unable to load example manifest in library test");
+
+    assert_eq!("org.hermione.example-package", manifest.id);
+}
+
+#[test]
+fn library_test_missing_manifest_is_a_manifest_error() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in library test");
+    let report = Manifest::new_from_path(temp_dir.path().join(Manifest::manifest_file_name()))
+        .expect_err("loading a missing manifest should fail");
+
+    assert_eq!(10, hermione::error::exit_code(&report));
+}
+
+#[test]
+fn library_test_load_toml_and_json_manifests() {
+    let example =
+        Manifest::new_from_path(Path::new("example-package").join(Manifest::manifest_file_name()))
+            .expect("unable to load example manifest in library test");
+    let temp_dir = TempDir::new().expect("unable to create temp dir in library test");
+
+    for format in &[ManifestFormat::Toml, ManifestFormat::Json] {
+        let package_dir = temp_dir.path().join(format.to_string());
+        fs::create_dir(&package_dir).expect("unable to create package dir in library test");
+        fs::write(
+            package_dir.join(format.file_name()),
+            format
+                .serialize(&example)
+                .expect("unable to serialize manifest in library test"),
+        )
+        .expect("unable to write manifest in library test");
+
+        let manifest =
+            Manifest::new_from_dir(&package_dir).expect("unable to load manifest in library test");
+        assert_eq!(example, manifest);
+    }
+
+    fs::copy(
+        Path::new("example-package").join("hermione.yml"),
+        temp_dir.path().join("toml").join("hermione.yml"),
+    )
+    .expect("unable to copy manifest in library test");
+    let report = Manifest::new_from_dir(&temp_dir.path().join("toml"))
+        .expect_err("loading a directory with two manifests should fail");
+    assert_eq!(10, hermione::error::exit_code(&report));
+}
+
+#[test]
+fn library_test_published_schema_is_current() {
+    let published = fs::read_to_string("hermione.schema.json")
+        .expect("unable to read published schema in library test");
+
+    assert_eq!(
+        Manifest::json_schema().expect("unable to generate schema in library test"),
+        published.trim_end(),
+        "hermione.schema.json is stale, regenerate it with `herm schema > hermione.schema.json`"
+    );
+}
+
+/// Collects events so tests can assert on what an operation reported.
+#[derive(Debug, Default)]
+struct RecordingSink {
+    events: Mutex<Vec<Event>>,
+}
+
+impl EventSink for RecordingSink {
+    fn emit(&self, event: Event) {
+        self.events
+            .lock()
+            .expect("unable to record event in library test")
+            .push(event);
+    }
+}
+
+#[test]
+fn library_test_install_reports_events() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in library test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in library test");
+    env::set_var("XDG_DATA_HOME", temp_dir.path());
+    env::set_var("XDG_CACHE_HOME", temp_dir.path().join("cache"));
+    env::set_var("HOME", test_home_dir.path());
+
+    let sink = Arc::new(RecordingSink::default());
+    let mut package_service =
+        PackageService::new().expect("unable to create package service in library test");
+    package_service.events = sink.clone();
+    package_service
+        .init()
+        .expect("unable to init package service in library test");
+    package_service
+        .download_and_install(String::from("file://./example-package"))
+        .expect("unable to install example package in library test");
+
+    let events = sink
+        .events
+        .lock()
+        .expect("unable to read events in library test");
+    assert!(events.contains(&Event::MappingLinked {
+        input: temp_dir
+            .path()
+            .join("herm")
+            .join("org.hermione.example-package")
+            .join("0.1.0")
+            .join("b.txt"),
+        output: test_home_dir.path().join("bamboo.txt"),
+    }));
+    assert!(events.contains(&Event::HookOutput {
+        hook: String::from("pre_install"),
+        line: String::from("Hello from pre_install hook"),
+    }));
+}
@@ -1,7 +1,6 @@
 use assert_cmd::Command;
 use assert_fs::prelude::*;
 use assert_fs::TempDir;
-use predicates;
 use predicates::prelude::*;
 
 use std::fs;
@@ -33,8 +32,8 @@ fn smoke_test_list_invocation() {
 
     let mut cmd = Command::cargo_bin("herm").unwrap();
     cmd.arg("list")
-        .env("XDG_DATA_HOME", &temp_dir_path)
-        .env("HOME", &test_home_dir.path())
+        .env("XDG_DATA_HOME", temp_dir_path)
+        .env("HOME", test_home_dir.path())
         .assert()
         .append_context("main", "list")
         .success()
@@ -61,6 +60,40 @@ fn smoke_test_init_invocation() {
         .expect("unable to clean up hermione.yml file after smoke test");
 }
 
+#[test]
+fn smoke_test_init_toml_manifest() {
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("init")
+        .arg("--format")
+        .arg("toml")
+        .current_dir(package_dir.path())
+        .assert()
+        .success();
+    package_dir
+        .child("hermione.toml")
+        .assert(predicate::str::contains("[[mappings]]\ni = \"sample.txt\""));
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("init")
+        .current_dir(package_dir.path())
+        .assert()
+        .failure();
+    package_dir
+        .child("hermione.yml")
+        .assert(predicate::path::missing());
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("lint")
+        .arg(package_dir.path())
+        .assert()
+        .code(10)
+        .stderr(predicate::str::contains(
+            "hermione.toml:9: Mapping input sample.txt does not exist",
+        ));
+}
+
 #[test]
 fn smoke_test_install_example_package() {
     let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
@@ -75,8 +108,8 @@ fn smoke_test_install_example_package() {
     let mut cmd = Command::cargo_bin("herm").unwrap();
     cmd.arg("install")
         .arg("file://./example-package")
-        .env("XDG_DATA_HOME", &temp_dir_path)
-        .env("HOME", &test_home_dir.path())
+        .env("XDG_DATA_HOME", temp_dir_path)
+        .env("HOME", test_home_dir.path())
         .assert()
         .append_context("main", "install file://./example-package")
         .success();
@@ -101,8 +134,8 @@ fn smoke_test_pre_install_hook() {
     let mut cmd = Command::cargo_bin("herm").unwrap();
     cmd.arg("install")
         .arg("file://./example-package")
-        .env("XDG_DATA_HOME", &temp_dir_path)
-        .env("HOME", &test_home_dir.path())
+        .env("XDG_DATA_HOME", temp_dir_path)
+        .env("HOME", test_home_dir.path())
         .assert()
         .append_context("main", "install file://./example-package")
         .stdout(predicate::str::contains("Hello from pre_install hook"));
@@ -118,8 +151,8 @@ fn smoke_test_post_install_hook() {
     let mut cmd = Command::cargo_bin("herm").unwrap();
     cmd.arg("install")
         .arg("file://./example-package")
-        .env("XDG_DATA_HOME", &temp_dir_path)
-        .env("HOME", &test_home_dir.path())
+        .env("XDG_DATA_HOME", temp_dir_path)
+        .env("HOME", test_home_dir.path())
         .assert()
         .append_context("main", "install file://./example-package")
         .stdout(predicate::str::contains("Hello from post_install hook"));
@@ -136,8 +169,8 @@ fn smoke_test_pre_remove_hook() {
     install_cmd
         .arg("install")
         .arg("file://./example-package")
-        .env("XDG_DATA_HOME", &temp_dir_path)
-        .env("HOME", &test_home_dir.path())
+        .env("XDG_DATA_HOME", temp_dir_path)
+        .env("HOME", test_home_dir.path())
         .assert()
         .append_context("main", "install file://./example-package")
         .success();
@@ -145,8 +178,8 @@ fn smoke_test_pre_remove_hook() {
     let mut cmd = Command::cargo_bin("herm").unwrap();
     cmd.arg("remove")
         .arg("org.hermione.example-package")
-        .env("XDG_DATA_HOME", &temp_dir_path)
-        .env("HOME", &test_home_dir.path())
+        .env("XDG_DATA_HOME", temp_dir_path)
+        .env("HOME", test_home_dir.path())
         .assert()
         .append_context("main", "remove org.hermione.example-package")
         .stdout(predicate::str::contains("Hello from pre_remove hook"));
@@ -163,8 +196,8 @@ fn smoke_test_post_remove_hook() {
     install_cmd
         .arg("install")
         .arg("file://./example-package")
-        .env("XDG_DATA_HOME", &temp_dir_path)
-        .env("HOME", &test_home_dir.path())
+        .env("XDG_DATA_HOME", temp_dir_path)
+        .env("HOME", test_home_dir.path())
         .assert()
         .append_context("main", "install file://./example-package")
         .success();
@@ -172,9 +205,1516 @@ fn smoke_test_post_remove_hook() {
     let mut cmd = Command::cargo_bin("herm").unwrap();
     cmd.arg("remove")
         .arg("org.hermione.example-package")
-        .env("XDG_DATA_HOME", &temp_dir_path)
-        .env("HOME", &test_home_dir.path())
+        .env("XDG_DATA_HOME", temp_dir_path)
+        .env("HOME", test_home_dir.path())
         .assert()
         .append_context("main", "remove org.hermione.example-package")
         .stdout(predicate::str::contains("Hello from post_remove hook"));
 }
+
+#[test]
+fn smoke_test_install_refuses_existing_file_by_default() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    test_home_dir
+        .child("bamboo.txt")
+        .write_str("mine")
+        .expect("unable to write existing file in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg("file://./example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "install file://./example-package")
+        .failure();
+
+    test_home_dir.child("bamboo.txt").assert("mine");
+}
+
+#[test]
+fn smoke_test_install_backup_and_restore_on_remove() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    test_home_dir
+        .child("bamboo.txt")
+        .write_str("mine")
+        .expect("unable to write existing file in smoke test");
+
+    let mut install_cmd = Command::cargo_bin("herm").unwrap();
+    install_cmd
+        .arg("install")
+        .arg("file://./example-package")
+        .arg("--on-conflict")
+        .arg("backup")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "install --on-conflict backup")
+        .success();
+
+    assert!(
+        fs::symlink_metadata(test_home_dir.child("bamboo.txt").path())
+            .expect("unable to read installed file in smoke test")
+            .file_type()
+            .is_symlink()
+    );
+
+    let mut list_cmd = Command::cargo_bin("herm").unwrap();
+    list_cmd
+        .arg("backups")
+        .arg("list")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "backups list")
+        .success()
+        .stdout(predicate::str::contains("Displayed: 1 Backups"));
+
+    let mut remove_cmd = Command::cargo_bin("herm").unwrap();
+    remove_cmd
+        .arg("remove")
+        .arg("org.hermione.example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "remove org.hermione.example-package")
+        .success();
+
+    test_home_dir.child("bamboo.txt").assert("mine");
+}
+
+#[test]
+fn smoke_test_adopt_existing_file() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .child("hermione.yml")
+        .write_str(
+            "name: Dots\nauthors: [me]\ndescription: dots\nid: org.example.dots\nversion: 0.1.0\nmappings: []\nhooks: ~\n",
+        )
+        .expect("unable to write manifest in smoke test");
+    test_home_dir
+        .child(".foorc")
+        .write_str("mine")
+        .expect("unable to write existing file in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("adopt")
+        .arg(test_home_dir.child(".foorc").path())
+        .arg("--into")
+        .arg(package_dir.path())
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "adopt")
+        .success();
+
+    package_dir.child(".foorc").assert("mine");
+    package_dir
+        .child("hermione.yml")
+        .assert(predicate::str::contains("{{HOME}}/.foorc"));
+    assert!(fs::symlink_metadata(test_home_dir.child(".foorc").path())
+        .expect("unable to read adopted file in smoke test")
+        .file_type()
+        .is_symlink());
+    test_home_dir.child(".foorc").assert("mine");
+}
+
+#[test]
+fn smoke_test_adopt_restores_previous_version_on_failure() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .child("a.txt")
+        .write_str("a")
+        .expect("unable to write package file in smoke test");
+    package_dir
+        .child("hermione.yml")
+        .write_str(
+            "name: Dots\nauthors: [me]\ndescription: dots\nid: org.example.dots\nversion: 0.1.0\nmappings:\n  - i: a.txt\n    o: \"{{HOME}}/a.txt\"\n    integrity: sha256-ypeBEsobvcr6wjGzmiPcTaeG7/gUfE5yuYB3ha/uSLs=\nhooks: ~\n",
+        )
+        .expect("unable to write manifest in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg(format!("file://{}", package_dir.path().display()))
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "install")
+        .success();
+    test_home_dir.child("a.txt").assert("a");
+
+    // The next version records the wrong integrity, so reinstalling it fails
+    package_dir
+        .child("hermione.yml")
+        .write_str(
+            "name: Dots\nauthors: [me]\ndescription: dots\nid: org.example.dots\nversion: 0.2.0\nmappings:\n  - i: a.txt\n    o: \"{{HOME}}/a.txt\"\n    integrity: sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=\nhooks: ~\n",
+        )
+        .expect("unable to write manifest in smoke test");
+    test_home_dir
+        .child(".foorc")
+        .write_str("mine")
+        .expect("unable to write existing file in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("adopt")
+        .arg(test_home_dir.child(".foorc").path())
+        .arg("--into")
+        .arg(package_dir.path())
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "adopt")
+        .failure();
+
+    test_home_dir.child(".foorc").assert("mine");
+    test_home_dir.child("a.txt").assert("a");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("list")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "list")
+        .success()
+        .stdout(predicate::str::contains("0.1.0"));
+}
+
+#[test]
+fn smoke_test_verify_detects_missing_link() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+
+    let mut install_cmd = Command::cargo_bin("herm").unwrap();
+    install_cmd
+        .arg("install")
+        .arg("file://./example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "install file://./example-package")
+        .success();
+
+    let mut verify_cmd = Command::cargo_bin("herm").unwrap();
+    verify_cmd
+        .arg("verify")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "verify")
+        .success();
+
+    fs::remove_file(test_home_dir.child("bamboo.txt").path())
+        .expect("unable to remove installed file in smoke test");
+
+    let mut drift_cmd = Command::cargo_bin("herm").unwrap();
+    drift_cmd
+        .arg("verify")
+        .arg("org.hermione.example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "verify org.hermione.example-package")
+        .code(18)
+        .stdout(predicate::str::contains("missing"));
+}
+
+#[test]
+fn smoke_test_repair_restores_drift() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+
+    let mut install_cmd = Command::cargo_bin("herm").unwrap();
+    install_cmd
+        .arg("install")
+        .arg("file://./example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "install file://./example-package")
+        .success();
+
+    fs::remove_file(test_home_dir.child("bamboo.txt").path())
+        .expect("unable to remove installed file in smoke test");
+    temp_dir
+        .child("herm")
+        .child("org.hermione.example-package")
+        .child("b.txt")
+        .write_str("tampered")
+        .expect("unable to modify installed package in smoke test");
+
+    let mut repair_cmd = Command::cargo_bin("herm").unwrap();
+    repair_cmd
+        .arg("repair")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "repair")
+        .success()
+        .stdout(predicate::str::contains("Hello from pre_install hook").not());
+
+    let mut verify_cmd = Command::cargo_bin("herm").unwrap();
+    verify_cmd
+        .arg("verify")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "verify")
+        .success();
+}
+
+#[test]
+fn smoke_test_skipped_mapping_is_left_alone() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    test_home_dir
+        .child("bamboo.txt")
+        .write_str("mine")
+        .expect("unable to write existing file in smoke test");
+
+    let mut install_cmd = Command::cargo_bin("herm").unwrap();
+    install_cmd
+        .arg("install")
+        .arg("file://./example-package")
+        .arg("--on-conflict")
+        .arg("skip")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "install --on-conflict skip")
+        .success();
+
+    let mut verify_cmd = Command::cargo_bin("herm").unwrap();
+    verify_cmd
+        .arg("verify")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "verify")
+        .success()
+        .stdout(predicate::str::contains("skipped"));
+
+    let mut repair_cmd = Command::cargo_bin("herm").unwrap();
+    repair_cmd
+        .arg("repair")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "repair")
+        .success();
+
+    test_home_dir.child("bamboo.txt").assert("mine");
+    assert!(
+        !fs::symlink_metadata(test_home_dir.child("bamboo.txt").path())
+            .expect("unable to read skipped file in smoke test")
+            .file_type()
+            .is_symlink()
+    );
+}
+
+#[test]
+fn smoke_test_dry_run_install_changes_nothing() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("--dry-run=json")
+        .arg("install")
+        .arg("file://./example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "--dry-run=json install file://./example-package")
+        .success()
+        .stdout(predicate::str::contains("\"operation\": \"link\""));
+
+    temp_dir.child("herm").assert(predicate::path::missing());
+    test_home_dir
+        .child(".cache")
+        .child("herm")
+        .assert(predicate::path::missing());
+    test_home_dir
+        .child("bamboo.txt")
+        .assert(predicate::path::missing());
+}
+
+#[test]
+fn smoke_test_install_json_output() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    let assert = cmd
+        .arg("--output")
+        .arg("json")
+        .arg("install")
+        .arg("file://./example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "--output json install file://./example-package")
+        .success()
+        .stderr(predicate::str::contains("Hello from pre_install hook"));
+
+    let document: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)
+        .expect("stdout of --output json is not a JSON document");
+    assert_eq!(document["ok"], true);
+    assert_eq!(document["command"], "install");
+    assert_eq!(document["package"]["id"], "org.hermione.example-package");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    let assert = cmd
+        .arg("list")
+        .arg("--output=json")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "list --output=json")
+        .success();
+
+    let document: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)
+        .expect("stdout of --output json is not a JSON document");
+    assert_eq!(
+        document["packages"][0]["id"],
+        "org.hermione.example-package"
+    );
+}
+
+#[test]
+fn smoke_test_remove_unknown_package_fails_with_exit_code() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("remove")
+        .arg("org.hermione.not-installed")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .append_context("main", "remove org.hermione.not-installed")
+        .code(17)
+        .stderr(predicate::str::contains("isn't installed"));
+}
+
+#[test]
+fn smoke_test_install_with_root_and_target_home() {
+    let root_dir = TempDir::new().expect("unable to create temp root dir in smoke test");
+    let target_home_dir = TempDir::new().expect("unable to create temp target home in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg("file://./example-package")
+        .arg("--target-home")
+        .arg(target_home_dir.path())
+        .env("HERMIONE_HOME", root_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    root_dir
+        .child("data")
+        .child("org.hermione.example-package")
+        .assert(predicate::path::is_dir());
+    target_home_dir
+        .child("bamboo.txt")
+        .assert(predicate::path::exists());
+    test_home_dir
+        .child("bamboo.txt")
+        .assert(predicate::path::missing());
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("--root")
+        .arg(root_dir.path())
+        .arg("list")
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("org.hermione.example-package"));
+}
+
+#[test]
+fn smoke_test_sync_installs_and_prunes() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    temp_dir
+        .child("example-package")
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+    let hermfile = temp_dir.child("Hermfile");
+    hermfile
+        .write_str("packages:\n  - source: file://./example-package\n    version: \"^0.1\"\n")
+        .expect("unable to write Hermfile in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("sync")
+        .arg(hermfile.path())
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "org.hermione.example-package 0.1.0 from",
+        ));
+    test_home_dir
+        .child("bamboo.txt")
+        .assert(predicate::path::exists());
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("sync")
+        .arg(hermfile.path())
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Already in sync"));
+
+    hermfile
+        .write_str("packages: []\n")
+        .expect("unable to write Hermfile in smoke test");
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("sync")
+        .arg(hermfile.path())
+        .arg("--prune")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    test_home_dir
+        .child("bamboo.txt")
+        .assert(predicate::path::missing());
+}
+
+#[test]
+fn smoke_test_export_and_install_from_lock() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let other_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let other_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let lock = temp_dir.child("Hermfile.lock");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg("file://./example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("export")
+        .arg(lock.path())
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    lock.assert(predicate::str::contains("id: org.hermione.example-package"))
+        .assert(predicate::str::contains("=0.1.0"));
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg("--from-lock")
+        .arg(lock.path())
+        .env("XDG_DATA_HOME", other_dir.path())
+        .env("HOME", other_home_dir.path())
+        .assert()
+        .success();
+    other_home_dir
+        .child("bamboo.txt")
+        .assert(predicate::path::exists());
+}
+
+#[test]
+fn smoke_test_hermfile_rejects_unknown_keys() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let lock = temp_dir.child("Hermfile.lock");
+    lock.write_str(
+        "packages:\n  - source: file://./example-package\n    variables:\n      email: me@example.com\n",
+    )
+    .expect("unable to write Hermfile in smoke test");
+
+    for args in &[vec!["sync"], vec!["install", "--from-lock"]] {
+        let mut cmd = Command::cargo_bin("herm").unwrap();
+        cmd.args(args)
+            .arg(lock.path())
+            .env("XDG_DATA_HOME", temp_dir.path())
+            .env("HOME", test_home_dir.path())
+            .assert()
+            .code(10)
+            .stderr(predicate::str::contains("unknown field `variables`"));
+    }
+    test_home_dir
+        .child("bamboo.txt")
+        .assert(predicate::path::missing());
+}
+
+#[test]
+fn smoke_test_history_and_undo() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg("file://./example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("remove")
+        .arg("org.hermione.example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    test_home_dir
+        .child("bamboo.txt")
+        .assert(predicate::path::missing());
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("undo")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    test_home_dir
+        .child("bamboo.txt")
+        .assert(predicate::path::exists());
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("history")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("#1"))
+        .stdout(predicate::str::contains("(undid #2)"));
+}
+
+#[test]
+fn smoke_test_history_shows_failed_install() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .child("a.txt")
+        .write_str("a")
+        .expect("unable to write package file in smoke test");
+    package_dir
+        .child("hermione.yml")
+        .write_str(
+            "name: Dots\nauthors: [me]\ndescription: dots\nid: org.example.dots\nversion: 0.1.0\nmappings:\n  - i: a.txt\n    o: \"{{HOME}}/a.txt\"\n    integrity: sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=\nhooks: ~\n",
+        )
+        .expect("unable to write manifest in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg(format!("file://{}", package_dir.path().display()))
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .failure();
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("history")
+        .arg("org.example.dots")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("install org.example.dots"))
+        .stdout(predicate::str::contains("failed"));
+}
+
+#[test]
+fn smoke_test_rollback_to_kept_version() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+    let source = format!("file://{}", package_dir.path().display());
+    let package_path = temp_dir.child("herm").child("org.hermione.example-package");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg(&source)
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    let manifest = fs::read_to_string(package_dir.child("hermione.yml").path())
+        .expect("unable to read manifest in smoke test");
+    package_dir
+        .child("hermione.yml")
+        .write_str(&manifest.replace("version: 0.1.0", "version: 0.2.0"))
+        .expect("unable to bump version in smoke test");
+    let hermfile = temp_dir.child("Hermfile");
+    hermfile
+        .write_str(&format!(
+            "packages:\n  - source: {}\n    version: \"^0.2\"\n",
+            source
+        ))
+        .expect("unable to write Hermfile in smoke test");
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("sync")
+        .arg(hermfile.path())
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    assert_eq!(
+        package_path.child("0.2.0").child("b.txt").path(),
+        fs::read_link(test_home_dir.child("bamboo.txt").path())
+            .expect("unable to read link in smoke test")
+    );
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("rollback")
+        .arg("org.hermione.example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    assert_eq!(
+        package_path.child("0.1.0").child("b.txt").path(),
+        fs::read_link(test_home_dir.child("bamboo.txt").path())
+            .expect("unable to read link in smoke test")
+    );
+    assert_eq!(
+        Path::new("0.1.0"),
+        fs::read_link(package_path.child("current").path())
+            .expect("unable to read current version in smoke test")
+    );
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("rollback")
+        .arg("org.hermione.example-package")
+        .arg("--to")
+        .arg("0.3.0")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .code(17);
+}
+
+#[test]
+fn smoke_test_pin_holds_package_during_sync() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+    let source = format!("file://{}", package_dir.path().display());
+    let package_path = temp_dir.child("herm").child("org.hermione.example-package");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg(&source)
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("pin")
+        .arg("org.hermione.example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("list")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pinned =0.1.0"));
+
+    let manifest = fs::read_to_string(package_dir.child("hermione.yml").path())
+        .expect("unable to read manifest in smoke test");
+    package_dir
+        .child("hermione.yml")
+        .write_str(&manifest.replace("version: 0.1.0", "version: 0.2.0"))
+        .expect("unable to bump version in smoke test");
+    let hermfile = temp_dir.child("Hermfile");
+    hermfile
+        .write_str(&format!(
+            "packages:\n  - source: {}\n    version: \"^0.2\"\n",
+            source
+        ))
+        .expect("unable to write Hermfile in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("sync")
+        .arg(hermfile.path())
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    package_path
+        .child("0.2.0")
+        .assert(predicate::path::missing());
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("unpin")
+        .arg("org.hermione.example-package")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("sync")
+        .arg(hermfile.path())
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    package_path
+        .child("0.2.0")
+        .assert(predicate::path::is_dir());
+}
+
+#[test]
+fn smoke_test_outdated_fails_when_source_is_newer() {
+    let root_dir = TempDir::new().expect("unable to create root dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("--root")
+        .arg(root_dir.path())
+        .arg("install")
+        .arg(format!("file://{}", package_dir.path().display()))
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("--root")
+        .arg(root_dir.path())
+        .arg("outdated")
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    let manifest = fs::read_to_string(package_dir.child("hermione.yml").path())
+        .expect("unable to read manifest in smoke test");
+    package_dir
+        .child("hermione.yml")
+        .write_str(&manifest.replace("version: 0.1.0", "version: 0.2.0"))
+        .expect("unable to bump version in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    let assert = cmd
+        .arg("--root")
+        .arg(root_dir.path())
+        .arg("--output")
+        .arg("json")
+        .arg("outdated")
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .code(19);
+
+    let document: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)
+        .expect("stdout of --output json is not a JSON document");
+    let package = &document["packages"][0];
+    assert_eq!(package["id"], "org.hermione.example-package");
+    assert_eq!(package["current"], "0.1.0");
+    assert_eq!(package["latest"], "0.2.0");
+    assert_eq!(package["outdated"], true);
+}
+
+#[test]
+fn smoke_test_info_for_directory_and_installed_package() {
+    let root_dir = TempDir::new().expect("unable to create root dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    let assert = cmd
+        .arg("--root")
+        .arg(root_dir.path())
+        .arg("--output")
+        .arg("json")
+        .arg("info")
+        .arg("example-package")
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    let document: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)
+        .expect("stdout of --output json is not a JSON document");
+    assert_eq!(document["package"]["origin"], "directory");
+    assert_eq!(document["package"]["installed_at"], serde_json::Value::Null);
+    assert_eq!(document["package"]["hooks"][0], "pre_install");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("--root")
+        .arg(root_dir.path())
+        .arg("install")
+        .arg("file://./example-package")
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    let assert = cmd
+        .arg("--root")
+        .arg(root_dir.path())
+        .arg("--output")
+        .arg("json")
+        .arg("info")
+        .arg("org.hermione.example-package")
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    let document: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)
+        .expect("stdout of --output json is not a JSON document");
+    let package = &document["package"];
+    assert_eq!(package["origin"], "installed");
+    assert_eq!(package["name"], "Example Package");
+    assert!(package["installed_at"].is_string());
+    assert_eq!(package["mappings"][1]["state"], "ok");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("--root")
+        .arg(root_dir.path())
+        .arg("info")
+        .arg("org.example.unknown")
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .code(17);
+}
+
+#[test]
+fn smoke_test_lint_reports_problems() {
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("lint").arg(package_dir.path()).assert().success();
+
+    fs::remove_file(package_dir.child("b.txt").path())
+        .expect("unable to remove mapping input in smoke test");
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("lint")
+        .arg(package_dir.path())
+        .assert()
+        .code(10)
+        .stderr(predicate::str::contains(
+            "hermione.yml:13: Mapping input b.txt does not exist",
+        ));
+}
+
+#[test]
+fn smoke_test_migrate_manifest_in_place() {
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("migrate-manifest")
+        .arg(package_dir.path())
+        .assert()
+        .success();
+    package_dir
+        .child("hermione.yml")
+        .assert(predicate::str::contains(
+            "manifest_version: 1\nname: Example Package",
+        ));
+
+    let manifest = fs::read_to_string(package_dir.child("hermione.yml").path())
+        .expect("unable to read manifest in smoke test");
+    package_dir
+        .child("hermione.yml")
+        .write_str(&manifest.replace("manifest_version: 1", "manifest_version: 99"))
+        .expect("unable to write manifest in smoke test");
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("migrate-manifest")
+        .arg(package_dir.path())
+        .assert()
+        .code(10);
+}
+
+#[test]
+fn smoke_test_deterministic_package_is_reproducible() {
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+    let archive_name = "org.hermione.example-package_0.1.0.hpkg";
+
+    let mut archives = Vec::new();
+    for _ in 0..2 {
+        let out_dir = TempDir::new().expect("unable to create out dir in smoke test");
+        let mut cmd = Command::cargo_bin("herm").unwrap();
+        cmd.arg("package")
+            .arg(package_dir.path())
+            .env("SOURCE_DATE_EPOCH", "1600000000")
+            .current_dir(out_dir.path())
+            .assert()
+            .success();
+        archives.push(
+            fs::read(out_dir.child(archive_name).path())
+                .expect("unable to read archive in smoke test"),
+        );
+
+        package_dir
+            .child("b.txt")
+            .write_str("")
+            .expect("unable to touch mapping input in smoke test");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::set_permissions(
+                package_dir.child("b.txt").path(),
+                fs::Permissions::from_mode(0o600),
+            )
+            .expect("unable to change mode of mapping input in smoke test");
+        }
+    }
+    assert_eq!(archives[0], archives[1]);
+
+    let out_dir = TempDir::new().expect("unable to create out dir in smoke test");
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("package")
+        .arg(package_dir.path())
+        .arg("--deterministic")
+        .env_remove("SOURCE_DATE_EPOCH")
+        .current_dir(out_dir.path())
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("inspect")
+        .arg(out_dir.child(archive_name).path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-0644          0 a.txt"));
+}
+
+#[test]
+fn smoke_test_package_leaves_source_manifest_alone() {
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+    let manifest = fs::read_to_string(package_dir.child("hermione.yml").path())
+        .expect("unable to read manifest in smoke test");
+    let manifest = manifest
+        .lines()
+        .filter(|line| !line.contains("integrity:"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    package_dir
+        .child("hermione.yml")
+        .write_str(&manifest)
+        .expect("unable to write manifest in smoke test");
+    let out_dir = TempDir::new().expect("unable to create out dir in smoke test");
+    let archive = out_dir.child("dist").child("example.hpkg");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("package")
+        .arg(package_dir.path())
+        .arg("--out")
+        .arg(archive.path())
+        .assert()
+        .success();
+    package_dir.child("hermione.yml").assert(manifest.as_str());
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("inspect")
+        .arg(archive.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "integrity: sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=",
+        ));
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("package")
+        .arg(package_dir.path())
+        .arg("--out-dir")
+        .arg(out_dir.path())
+        .arg("--write-integrity")
+        .assert()
+        .success();
+    out_dir
+        .child("org.hermione.example-package_0.1.0.hpkg")
+        .assert(predicate::path::is_file());
+    package_dir
+        .child("hermione.yml")
+        .assert(predicate::str::contains(
+            "integrity: sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=",
+        ));
+}
+
+#[test]
+fn smoke_test_install_package_with_included_files() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+    let manifest = fs::read_to_string(package_dir.child("hermione.yml").path())
+        .expect("unable to read manifest in smoke test");
+    package_dir
+        .child("hermione.yml")
+        .write_str(&format!("{}\ninclude:\n  - \"notes/*\"\n", manifest))
+        .expect("unable to write manifest in smoke test");
+    package_dir
+        .child("notes")
+        .child("README.md")
+        .write_str("Read me")
+        .expect("unable to write included file in smoke test");
+    package_dir
+        .child("notes")
+        .child("scratch.md")
+        .write_str("Not for sharing")
+        .expect("unable to write excluded file in smoke test");
+    package_dir
+        .child(".hermignore")
+        .write_str("scratch.md\n")
+        .expect("unable to write .hermignore in smoke test");
+    let archive = temp_dir.child("example.hpkg");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("package")
+        .arg(package_dir.path())
+        .arg("--out")
+        .arg(archive.path())
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg(format!("file://{}", archive.path().display()))
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    let installed = temp_dir
+        .child("herm")
+        .child("org.hermione.example-package")
+        .child("0.1.0");
+    installed
+        .child("notes")
+        .child("README.md")
+        .assert("Read me");
+    installed
+        .child("notes")
+        .child("scratch.md")
+        .assert(predicate::path::missing());
+}
+
+#[test]
+fn smoke_test_install_archive_with_nested_manifest_name() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+    let manifest = fs::read_to_string(package_dir.child("hermione.yml").path())
+        .expect("unable to read manifest in smoke test");
+    package_dir
+        .child("hermione.yml")
+        .write_str(&format!("{}\ninclude:\n  - \"docs/**\"\n", manifest))
+        .expect("unable to write manifest in smoke test");
+    package_dir
+        .child("docs")
+        .child("hermione.yml")
+        .write_str("name: Not the manifest\n")
+        .expect("unable to write nested manifest name in smoke test");
+    let archive = temp_dir.child("demo.hpkg");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("package")
+        .arg(package_dir.path())
+        .arg("--out")
+        .arg(archive.path())
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("inspect").arg(archive.path()).assert().success();
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg(format!("file://{}", archive.path().display()))
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    temp_dir
+        .child("herm")
+        .child("org.hermione.example-package")
+        .child("0.1.0")
+        .child("docs")
+        .child("hermione.yml")
+        .assert("name: Not the manifest\n");
+}
+
+#[test]
+fn smoke_test_package_into_package_dir_leaves_archives_out() {
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+    let manifest = fs::read_to_string(package_dir.child("hermione.yml").path())
+        .expect("unable to read manifest in smoke test");
+    package_dir
+        .child("hermione.yml")
+        .write_str(&format!("{}\ninclude:\n  - \"**\"\n", manifest))
+        .expect("unable to write manifest in smoke test");
+    package_dir
+        .child("LICENSE")
+        .write_str("Apache-2.0")
+        .expect("unable to write included file in smoke test");
+    let archive = package_dir.child("org.hermione.example-package_0.1.0.hpkg");
+
+    for _ in 0..2 {
+        let mut cmd = Command::cargo_bin("herm").unwrap();
+        cmd.arg("package")
+            .current_dir(package_dir.path())
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("herm").unwrap();
+        cmd.arg("inspect")
+            .arg(archive.path())
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("LICENSE"))
+            .stdout(predicate::str::contains(" org.hermione.example-package_0.1.0.hpkg").not());
+    }
+}
+
+#[test]
+fn smoke_test_upgrade_respects_range_pin() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+    let source = format!("file://{}", package_dir.path().display());
+    let package_path = temp_dir.child("herm").child("org.hermione.example-package");
+    let manifest = fs::read_to_string(package_dir.child("hermione.yml").path())
+        .expect("unable to read manifest in smoke test");
+    let release = |version: &str| {
+        package_dir
+            .child("hermione.yml")
+            .write_str(&manifest.replace("version: 0.1.0", &format!("version: {}", version)))
+            .expect("unable to bump version in smoke test");
+    };
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg(&source)
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("pin")
+        .arg("org.hermione.example-package")
+        .arg("~0.1")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    release("0.1.1");
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("upgrade")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    package_path
+        .child("0.1.1")
+        .assert(predicate::path::is_dir());
+
+    release("0.2.0");
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("upgrade")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Holding org.hermione.example-package at 0.1.1",
+        ));
+    package_path
+        .child("0.2.0")
+        .assert(predicate::path::missing());
+    assert_eq!(
+        package_path.child("0.1.1").child("b.txt").path(),
+        fs::read_link(test_home_dir.child("bamboo.txt").path())
+            .expect("unable to read link in smoke test")
+    );
+}
+
+#[test]
+fn smoke_test_upgrade_only_installs_newer_versions() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+    let dots_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    dots_dir
+        .child("hermione.yml")
+        .write_str(
+            "name: Dots\nauthors: [me]\ndescription: dots\nid: org.example.dots\nversion: 0.1.0\nmappings: []\nhooks: ~\n",
+        )
+        .expect("unable to write manifest in smoke test");
+    let package_path = temp_dir.child("herm").child("org.hermione.example-package");
+    let manifest = fs::read_to_string(package_dir.child("hermione.yml").path())
+        .expect("unable to read manifest in smoke test");
+    let release = |version: &str| {
+        package_dir
+            .child("hermione.yml")
+            .write_str(&manifest.replace("version: 0.1.0", &format!("version: {}", version)))
+            .expect("unable to bump version in smoke test");
+    };
+    for source in &[package_dir.path(), dots_dir.path()] {
+        let mut cmd = Command::cargo_bin("herm").unwrap();
+        cmd.arg("install")
+            .arg(format!("file://{}", source.display()))
+            .env("XDG_DATA_HOME", temp_dir.path())
+            .env("HOME", test_home_dir.path())
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("upgrade")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "org.hermione.example-package is up to date at 0.1.0",
+        ));
+
+    release("0.0.9");
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("upgrade")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+    package_path
+        .child("0.0.9")
+        .assert(predicate::path::missing());
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("history")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("upgrade").not());
+
+    release("0.2.0");
+    dots_dir
+        .close()
+        .expect("unable to remove package dir in smoke test");
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("upgrade")
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Unable to check the source of org.example.dots",
+        ));
+    package_path
+        .child("0.2.0")
+        .assert(predicate::path::is_dir());
+}
+
+#[test]
+fn smoke_test_failed_upgrade_restores_previous_version() {
+    for command in &["upgrade", "sync"] {
+        let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+        let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+        let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+        package_dir
+            .copy_from("example-package", &["*"])
+            .expect("unable to copy example package in smoke test");
+        let source = format!("file://{}", package_dir.path().display());
+        let package_path = temp_dir.child("herm").child("org.hermione.example-package");
+
+        let mut cmd = Command::cargo_bin("herm").unwrap();
+        cmd.arg("install")
+            .arg(&source)
+            .env("XDG_DATA_HOME", temp_dir.path())
+            .env("HOME", test_home_dir.path())
+            .assert()
+            .success();
+
+        // 0.2.0 ships a mapping input which does not match its integrity
+        let manifest = fs::read_to_string(package_dir.child("hermione.yml").path())
+            .expect("unable to read manifest in smoke test");
+        package_dir
+            .child("hermione.yml")
+            .write_str(&manifest.replace("version: 0.1.0", "version: 0.2.0"))
+            .expect("unable to bump version in smoke test");
+        package_dir
+            .child("b.txt")
+            .write_str("tampered")
+            .expect("unable to tamper with mapping input in smoke test");
+        let hermfile = package_dir.child("Hermfile");
+        hermfile
+            .write_str(&format!(
+                "packages:\n  - source: {}\n    version: \"0.2.0\"\n",
+                source
+            ))
+            .expect("unable to write Hermfile in smoke test");
+
+        let mut cmd = Command::cargo_bin("herm").unwrap();
+        cmd.arg(command);
+        if *command == "sync" {
+            cmd.arg(hermfile.path());
+        }
+        cmd.env("XDG_DATA_HOME", temp_dir.path())
+            .env("HOME", test_home_dir.path())
+            .assert()
+            .append_context("main", command)
+            .failure();
+
+        assert_eq!(
+            Path::new("0.1.0"),
+            fs::read_link(package_path.child("current").path())
+                .expect("unable to read current link in smoke test")
+        );
+        assert_eq!(
+            package_path.child("0.1.0").child("b.txt").path(),
+            fs::read_link(test_home_dir.child("bamboo.txt").path())
+                .expect("unable to read link in smoke test")
+        );
+    }
+}
+
+#[test]
+fn smoke_test_search_json_output() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    let assert = cmd
+        .arg("--output")
+        .arg("json")
+        .arg("search")
+        .arg("dots")
+        .env("HERMIONE_HOME", temp_dir.path())
+        .assert()
+        .success();
+
+    let document: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)
+        .expect("stdout of --output json is not a JSON document");
+    assert_eq!(document["command"], "search");
+    assert_eq!(document["packages"], serde_json::json!([]));
+}
+
+#[test]
+fn smoke_test_sync_resolves_sources_against_hermfile() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let dotfiles_dir = TempDir::new().expect("unable to create dotfiles dir in smoke test");
+    dotfiles_dir
+        .child("example-package")
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+    let hermfile = dotfiles_dir.child("Hermfile");
+    hermfile
+        .write_str("packages:\n  - source: file://./example-package\n")
+        .expect("unable to write Hermfile in smoke test");
+    let elsewhere = TempDir::new().expect("unable to create working dir in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("sync")
+        .arg(hermfile.path())
+        .current_dir(elsewhere.path())
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    temp_dir
+        .child("herm")
+        .child("org.hermione.example-package")
+        .child("0.1.0")
+        .assert(predicate::path::is_dir());
+}
+
+#[test]
+fn smoke_test_sync_plans_downgrades_as_such() {
+    let temp_dir = TempDir::new().expect("unable to create temp dir in smoke test");
+    let test_home_dir = TempDir::new().expect("unable to create temp home dir in smoke test");
+    let package_dir = TempDir::new().expect("unable to create package dir in smoke test");
+    package_dir
+        .copy_from("example-package", &["*"])
+        .expect("unable to copy example package in smoke test");
+    let manifest = fs::read_to_string(package_dir.child("hermione.yml").path())
+        .expect("unable to read manifest in smoke test");
+    package_dir
+        .child("hermione.yml")
+        .write_str(&manifest.replace("version: 0.1.0", "version: 0.2.0"))
+        .expect("unable to bump version in smoke test");
+    let source = format!("file://{}", package_dir.path().display());
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("install")
+        .arg(&source)
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success();
+
+    package_dir
+        .child("hermione.yml")
+        .write_str(&manifest)
+        .expect("unable to write manifest in smoke test");
+    let hermfile = package_dir.child("Hermfile");
+    hermfile
+        .write_str(&format!(
+            "packages:\n  - source: {}\n    version: \"<0.2.0\"\n",
+            source
+        ))
+        .expect("unable to write Hermfile in smoke test");
+
+    let mut cmd = Command::cargo_bin("herm").unwrap();
+    cmd.arg("sync")
+        .arg(hermfile.path())
+        .env("XDG_DATA_HOME", temp_dir.path())
+        .env("HOME", test_home_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("downgrade"))
+        .stdout(predicate::str::contains("upgrade").not())
+        .stdout(predicate::str::contains("0.2.0 -> 0.1.0"));
+    temp_dir
+        .child("herm")
+        .child("org.hermione.example-package")
+        .child("0.1.0")
+        .assert(predicate::path::is_dir());
+}